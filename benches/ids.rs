@@ -0,0 +1,94 @@
+// Copyright (c) 2024 Gibson Pilconis, shibedrill, and contributors
+// SPDX-License-Identifier: BSD-3-Clause
+
+// Measures how fast devices can be named, across every entry of the
+// compiled-in database.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use libpci_rs::class::{all_classes, lookup_class};
+use libpci_rs::ids::{all_vendors, lookup_vendor};
+
+fn bench_device_lookup(c: &mut Criterion) {
+    let mut devices: Vec<(u16, u16)> = Vec::new();
+    for vendor in all_vendors() {
+        for device in vendor.devices().unwrap_or_default() {
+            devices.push((vendor.id(), device.id()));
+        }
+    }
+
+    let mut group = c.benchmark_group("ids");
+    group.throughput(Throughput::Elements(devices.len() as u64));
+    group.bench_function("device", |b| {
+        b.iter(|| {
+            for (vid, did) in &devices {
+                black_box(lookup_vendor(*vid).and_then(|x| x.device(*did).map(|x| x.name())));
+            }
+        })
+    });
+    group.finish();
+}
+
+fn bench_subsystem_lookup(c: &mut Criterion) {
+    let mut subsystems: Vec<(u16, u16, u16, u16)> = Vec::new();
+    for vendor in all_vendors() {
+        for device in vendor.devices().unwrap_or_default() {
+            for subsystem in device.subsystems().unwrap_or_default() {
+                subsystems.push((
+                    vendor.id(),
+                    device.id(),
+                    subsystem.subvendor(),
+                    subsystem.subdevice(),
+                ));
+            }
+        }
+    }
+
+    let mut group = c.benchmark_group("ids");
+    group.throughput(Throughput::Elements(subsystems.len() as u64));
+    group.bench_function("subsystem", |b| {
+        b.iter(|| {
+            for (vid, did, subvendor, subdevice) in &subsystems {
+                black_box(
+                    lookup_vendor(*vid)
+                        .and_then(|x| x.device(*did))
+                        .and_then(|x| x.subsystem(*subdevice, *subvendor).map(|x| x.name())),
+                );
+            }
+        })
+    });
+    group.finish();
+}
+
+fn bench_class_lookup(c: &mut Criterion) {
+    let mut progs: Vec<(u8, u8, u8)> = Vec::new();
+    for class in all_classes() {
+        for subclass in class.subclasses().unwrap_or_default() {
+            for prog in subclass.progs().unwrap_or_default() {
+                progs.push((class.id(), subclass.id(), prog.id()));
+            }
+        }
+    }
+
+    let mut group = c.benchmark_group("ids");
+    group.throughput(Throughput::Elements(progs.len() as u64));
+    group.bench_function("prog", |b| {
+        b.iter(|| {
+            for (class, subclass, prog) in &progs {
+                black_box(
+                    lookup_class(*class)
+                        .and_then(|x| x.subclass(*subclass))
+                        .and_then(|x| x.prog(*prog).map(|x| x.name())),
+                );
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_device_lookup,
+    bench_subsystem_lookup,
+    bench_class_lookup
+);
+criterion_main!(benches);
@@ -0,0 +1,115 @@
+// Copyright (c) 2024 Gibson Pilconis, shibedrill, and contributors
+// SPDX-License-Identifier: BSD-3-Clause
+
+// Encodes the database into the compact blob read by src/lib/ids/compact.rs.
+// The blob starts with the number of entries in each table as u32s, followed
+// by the tables themselves and a string table holding every name once. Each
+// table is made of fixed-size records, in the order of the parsed database,
+// and children are referred to by the index of their first record and their
+// count. All integers are little-endian.
+//
+// vendor:    id u16, name, first device u32, device count u32
+// device:    id u16, name, first subsystem u32, subsystem count u32
+// subsystem: subvendor u16, subdevice u16, name
+// class:     id u8, name, first subclass u32, subclass count u32
+// subclass:  id u8, name, first prog u32, prog count u32
+// prog:      id u8, name
+//
+// Names are stored as their offset in the string table as a u32 and their
+// length as a u16.
+
+use crate::parser::ParsedIds;
+use std::collections::HashMap;
+
+#[derive(Default)]
+struct Strings {
+    table: Vec<u8>,
+    offsets: HashMap<String, u32>,
+}
+
+impl Strings {
+    // Adds a name to the string table, reusing it if it is already there.
+    fn push(&mut self, record: &mut Vec<u8>, name: &str) {
+        let offset = *self.offsets.entry(name.to_string()).or_insert_with(|| {
+            let offset = self.table.len() as u32;
+            self.table.extend_from_slice(name.as_bytes());
+            offset
+        });
+        record.extend_from_slice(&offset.to_le_bytes());
+        record.extend_from_slice(&(name.len() as u16).to_le_bytes());
+    }
+}
+
+fn push_children(record: &mut Vec<u8>, first: usize, count: usize) {
+    record.extend_from_slice(&(first as u32).to_le_bytes());
+    record.extend_from_slice(&(count as u32).to_le_bytes());
+}
+
+pub fn encode(parsed: &ParsedIds) -> Vec<u8> {
+    let mut strings = Strings::default();
+    let mut vendors = Vec::new();
+    let mut devices = Vec::new();
+    let mut subsystems = Vec::new();
+    let mut classes = Vec::new();
+    let mut subclasses = Vec::new();
+    let mut progs = Vec::new();
+    let mut counts = [0usize; 6];
+
+    for vendor in &parsed.vendors {
+        vendors.extend_from_slice(&vendor.id.to_le_bytes());
+        strings.push(&mut vendors, &vendor.name);
+        push_children(&mut vendors, counts[1], vendor.devices.len());
+        counts[0] += 1;
+
+        for device in &vendor.devices {
+            devices.extend_from_slice(&device.id.to_le_bytes());
+            strings.push(&mut devices, &device.name);
+            push_children(&mut devices, counts[2], device.subsystems.len());
+            counts[1] += 1;
+
+            for subsystem in &device.subsystems {
+                subsystems.extend_from_slice(&subsystem.subvendor.to_le_bytes());
+                subsystems.extend_from_slice(&subsystem.subdevice.to_le_bytes());
+                strings.push(&mut subsystems, &subsystem.name);
+                counts[2] += 1;
+            }
+        }
+    }
+
+    for class in &parsed.classes {
+        classes.push(class.id);
+        strings.push(&mut classes, &class.name);
+        push_children(&mut classes, counts[4], class.subclasses.len());
+        counts[3] += 1;
+
+        for subclass in &class.subclasses {
+            subclasses.push(subclass.id);
+            strings.push(&mut subclasses, &subclass.name);
+            push_children(&mut subclasses, counts[5], subclass.progs.len());
+            counts[4] += 1;
+
+            for prog in &subclass.progs {
+                progs.push(prog.id);
+                strings.push(&mut progs, &prog.name);
+                counts[5] += 1;
+            }
+        }
+    }
+
+    let mut blob: Vec<u8> = counts
+        .iter()
+        .flat_map(|x| (*x as u32).to_le_bytes())
+        .collect();
+    for table in [
+        vendors,
+        devices,
+        subsystems,
+        classes,
+        subclasses,
+        progs,
+        strings.table,
+    ] {
+        blob.extend_from_slice(&table);
+    }
+    blob
+}
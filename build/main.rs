@@ -1,20 +1,36 @@
 // Copyright (c) 2024 Gibson Pilconis, shibedrill, and contributors
 // SPDX-License-Identifier: BSD-3-Clause
 
+#[cfg(feature = "pciids")]
+mod compact;
 mod cxx;
+// The pci.ids parser lives in the library so that it can also load pci.ids
+// at runtime.
+#[cfg(feature = "pciids")]
+#[allow(dead_code)]
+#[path = "../src/lib/ids/parser.rs"]
 mod parser;
+#[cfg(feature = "pciids")]
 pub mod types;
 
+#[cfg(feature = "pciids")]
 use std::env;
-use std::fs::File;
+#[cfg(feature = "pciids")]
+use std::fs::{self, File};
+#[cfg(feature = "pciids")]
 use std::io::{BufWriter, Write};
-use std::path::Path;
+#[cfg(feature = "pciids")]
+use std::path::{Path, PathBuf};
 
-use crate::parser::ingest_pciids;
+#[cfg(feature = "pciids")]
+use crate::parser::{ingest_pciids, ParsedIds};
+#[cfg(feature = "pciids")]
+use crate::types::PciIdsParsed;
 
 #[allow(unused_imports)]
 use crate::cxx::build_cxx_module;
 
+#[cfg(feature = "pciids")]
 fn generate_phf_data() {
     let devices_path = Path::new(&env::var("OUT_DIR").unwrap()).join("pci_devices_phf.rs");
     let classes_path = Path::new(&env::var("OUT_DIR").unwrap()).join("pci_classes_phf.rs");
@@ -22,23 +38,137 @@ fn generate_phf_data() {
     let mut devices_file = BufWriter::new(File::create(devices_path).unwrap());
     let mut classes_file = BufWriter::new(File::create(classes_path).unwrap());
 
-    let pci_ids_parsed = ingest_pciids(Path::new("pciids/pci.ids"));
+    let pciids_raw = read_pciids();
+    let mut parsed =
+        ingest_pciids(&pciids_raw).unwrap_or_else(|err| panic!("failed to parse pci.ids: {}", err));
+    trim_database(&mut parsed);
+    // The library binary searches the entries of each vendor and class.
+    parsed.sort();
 
-    writeln!(
-        devices_file,
-        "static VENDORS: phf::Map<u16, PciVendorEntry> = {};",
-        &pci_ids_parsed.pci.build()
-    )
-    .expect("failed to write VENDORS to registry!");
+    if cfg!(feature = "pciids-compact") {
+        let blob_path = Path::new(&env::var("OUT_DIR").unwrap()).join("pci_ids.bin");
+        fs::write(blob_path, compact::encode(&parsed)).expect("failed to write compact registry!");
 
-    writeln!(
-        classes_file,
-        "static CLASSES: phf::Map<u8, PciClassEntry> = {};",
-        &pci_ids_parsed.class.build()
-    )
-    .expect("failed to write CLASSES to registry!");
+        writeln!(devices_file, "{}", types::metadata(&parsed))
+            .expect("failed to write metadata to registry!");
+        // The classes are in the blob too.
+        writeln!(classes_file).expect("failed to write CLASSES to registry!");
+    } else {
+        let pci_ids_parsed = PciIdsParsed::from(parsed);
 
-    println!("cargo:rerun-if-changed=pciids/pci.ids");
+        writeln!(
+            devices_file,
+            "static VENDORS: phf::Map<u16, PciVendorEntry> = {};",
+            &pci_ids_parsed.pci.build()
+        )
+        .expect("failed to write VENDORS to registry!");
+
+        writeln!(devices_file, "{}", &pci_ids_parsed.metadata)
+            .expect("failed to write metadata to registry!");
+
+        writeln!(
+            classes_file,
+            "static CLASSES: phf::Map<u8, PciClassEntry> = {};",
+            &pci_ids_parsed.class.build()
+        )
+        .expect("failed to write CLASSES to registry!");
+    }
+}
+
+// Drops the entries the trimming features and the vendor allowlist exclude.
+#[cfg(feature = "pciids")]
+fn trim_database(parsed: &mut ParsedIds) {
+    println!("cargo:rerun-if-env-changed=LIBPCI_RS_PCIIDS_VENDORS");
+    if let Ok(allowlist) = env::var("LIBPCI_RS_PCIIDS_VENDORS") {
+        let allowed: Vec<u16> = allowlist
+            .split(|x: char| x == ',' || x.is_whitespace())
+            .filter(|x| !x.is_empty())
+            .map(|x| {
+                u16::from_str_radix(x.trim_start_matches("0x"), 16).unwrap_or_else(|_| {
+                    panic!("Invalid vendor ID {:?} in LIBPCI_RS_PCIIDS_VENDORS.", x)
+                })
+            })
+            .collect();
+        parsed.vendors.retain(|x| allowed.contains(&x.id));
+    }
+
+    if cfg!(feature = "pciids-vendors-only") {
+        for vendor in &mut parsed.vendors {
+            vendor.devices.clear();
+        }
+    }
+
+    if cfg!(feature = "pciids-no-subsystems") {
+        for device in parsed.vendors.iter_mut().flat_map(|x| &mut x.devices) {
+            device.subsystems.clear();
+        }
+    }
+}
+
+// The copies of pci.ids to build from, in order, unless LIBPCI_RS_PCI_IDS is
+// set. The submodule comes first, then the copies distributions ship.
+#[cfg(feature = "pciids")]
+const PCIIDS_PATHS: &[&str] = &[
+    "pciids/pci.ids",
+    #[cfg(feature = "gzip")]
+    "pciids/pci.ids.gz",
+    "/usr/share/hwdata/pci.ids",
+    #[cfg(feature = "gzip")]
+    "/usr/share/hwdata/pci.ids.gz",
+    "/usr/share/misc/pci.ids",
+    #[cfg(feature = "gzip")]
+    "/usr/share/misc/pci.ids.gz",
+];
+
+#[cfg(feature = "pciids")]
+fn pciids_path() -> PathBuf {
+    println!("cargo:rerun-if-env-changed=LIBPCI_RS_PCI_IDS");
+    if let Some(path) = env::var_os("LIBPCI_RS_PCI_IDS") {
+        return PathBuf::from(path);
+    }
+
+    // Pick up the submodule if it gets initialized later on.
+    if Path::new("pciids").exists() {
+        println!("cargo:rerun-if-changed=pciids");
+    }
+    PCIIDS_PATHS
+        .iter()
+        .map(PathBuf::from)
+        .find(|x| x.exists())
+        .expect(
+            "Failed to find PCI IDs. Initialize the repository submodules, install hwdata, \
+             or set LIBPCI_RS_PCI_IDS to the path of a pci.ids file.",
+        )
+}
+
+#[cfg(feature = "pciids")]
+fn read_pciids() -> String {
+    let path = pciids_path();
+    println!("cargo:rerun-if-changed={}", path.display());
+
+    let raw = fs::read(&path)
+        .unwrap_or_else(|err| panic!("Failed to read PCI IDs from {}: {}", path.display(), err));
+
+    // Gzip-compressed files start with these bytes.
+    if raw.starts_with(&[0x1f, 0x8b]) {
+        #[cfg(feature = "gzip")]
+        {
+            use std::io::Read;
+
+            let mut pciids_raw = String::new();
+            flate2::read::GzDecoder::new(raw.as_slice())
+                .read_to_string(&mut pciids_raw)
+                .expect("Failed to decompress PCI IDs.");
+            return pciids_raw;
+        }
+        #[cfg(not(feature = "gzip"))]
+        panic!(
+            "{} is compressed, enable the gzip feature to build from it.",
+            path.display()
+        );
+    }
+
+    String::from_utf8(raw).expect("PCI IDs are not valid UTF-8.")
 }
 
 fn main() {
@@ -1,75 +1,97 @@
 // Copyright (c) 2024 Gibson Pilconis, shibedrill, and contributors
 // SPDX-License-Identifier: BSD-3-Clause
 
+use crate::parser::*;
 use phf_codegen::Map;
 use proc_macro2::TokenStream;
 use quote::quote;
 
-pub struct PciVendorEntry {
-    pub(crate) id: u16,
-    pub(crate) name: String,
-    pub(crate) devices: Vec<PciDeviceEntry>,
-}
-
-pub struct PciDeviceEntry {
-    pub(crate) id: u16,
-    pub(crate) name: String,
-    pub(crate) subsystems: Vec<PciSubsystemEntry>,
-}
-
-pub struct PciSubsystemEntry {
-    pub(crate) subvendor: u16,
-    pub(crate) subdevice: u16,
-    pub(crate) name: String,
-}
+// A lot of inspiration for the overall architecture of this helper
+// (i.e. using PHF with quote) was taken from here:
+// https://github.com/lienching/pci-ids.rs/blob/main/build.rs
 
-pub struct PciClassEntry {
-    pub(crate) id: u8,
-    pub(crate) name: String,
-    pub(crate) subclasses: Vec<PciSubclassEntry>,
-}
-
-pub struct PciSubclassEntry {
-    pub(crate) id: u8,
-    pub(crate) name: String,
-    pub(crate) progs: Vec<PciProgEntry>,
+pub struct PciIdsParsed {
+    pub(crate) pci: Map<u16>,
+    pub(crate) class: Map<u8>,
+    pub(crate) metadata: TokenStream,
 }
 
-pub struct PciProgEntry {
-    pub(crate) id: u8,
-    pub(crate) name: String,
+impl From<ParsedIds> for PciIdsParsed {
+    fn from(parsed: ParsedIds) -> Self {
+        let mut pci = Map::new();
+        let mut class = Map::new();
+        let metadata = metadata(&parsed);
+
+        for vendor in parsed.vendors {
+            pci.entry(vendor.id, &quote!(#vendor).to_string());
+        }
+        for entry in parsed.classes {
+            class.entry(entry.id, &quote!(#entry).to_string());
+        }
+
+        PciIdsParsed {
+            pci,
+            class,
+            metadata,
+        }
+    }
 }
 
-pub struct PciIdsParsed {
-    pub(crate) pci: Map<u16>,
-    pub(crate) class: Map<u8>,
+// Generates the version, date and statistics of the database.
+pub fn metadata(parsed: &ParsedIds) -> TokenStream {
+    let optional = |value: &Option<String>| match value {
+        Some(value) => quote!(Some(#value)),
+        None => quote!(None),
+    };
+    let version = optional(&parsed.version);
+    let date = optional(&parsed.date);
+    let vendors = parsed.vendors.len();
+    let devices: usize = parsed.vendors.iter().map(|x| x.devices.len()).sum();
+    let subsystems: usize = parsed
+        .vendors
+        .iter()
+        .flat_map(|x| &x.devices)
+        .map(|x| x.subsystems.len())
+        .sum();
+    let classes = parsed.classes.len();
+
+    quote! {
+        static BUILTIN_VERSION: Option<&str> = #version;
+        static BUILTIN_DATE: Option<&str> = #date;
+        static BUILTIN_STATS: PciIdsStats = PciIdsStats {
+            vendors: #vendors,
+            devices: #devices,
+            subsystems: #subsystems,
+            classes: #classes,
+        };
+    }
 }
 
-impl quote::ToTokens for PciVendorEntry {
+impl quote::ToTokens for ParsedVendor {
     fn to_tokens(&self, tokens: &mut TokenStream) {
-        let PciVendorEntry { id, name, devices } = self;
+        let ParsedVendor { id, name, devices } = self;
 
         let devices = devices.iter().map(
-            |PciDeviceEntry {
+            |ParsedDevice {
                  id,
                  name,
                  subsystems,
              }| {
                 quote! {
-                    PciDeviceEntry { id: #id, name: #name, subsystems: &[#(#subsystems),*] }
+                    PciDeviceEntry { id: #id, name: #name, subsystems: Entries::Slice(&[#(#subsystems),*]) }
                 }
             },
         );
 
         tokens.extend(quote! {
-            PciVendorEntry { id: #id, name: #name, devices: &[#(#devices),*] }
+            PciVendorEntry { id: #id, name: #name, devices: Entries::Slice(&[#(#devices),*]) }
         });
     }
 }
 
-impl quote::ToTokens for PciSubsystemEntry {
+impl quote::ToTokens for ParsedSubsystem {
     fn to_tokens(&self, tokens: &mut TokenStream) {
-        let PciSubsystemEntry {
+        let ParsedSubsystem {
             subvendor,
             subdevice,
             name,
@@ -81,31 +103,29 @@ impl quote::ToTokens for PciSubsystemEntry {
     }
 }
 
-impl quote::ToTokens for PciClassEntry {
+impl quote::ToTokens for ParsedClass {
     fn to_tokens(&self, tokens: &mut TokenStream) {
-        let PciClassEntry {
+        let ParsedClass {
             id,
             name,
             subclasses,
         } = self;
 
-        let subclasses = subclasses
-            .iter()
-            .map(|PciSubclassEntry { id, name, progs }| {
-                quote! {
-                    PciSubclassEntry { id: #id, name: #name, progs: &[#(#progs),*] }
-                }
-            });
+        let subclasses = subclasses.iter().map(|ParsedSubclass { id, name, progs }| {
+            quote! {
+                PciSubclassEntry { id: #id, name: #name, progs: Entries::Slice(&[#(#progs),*]) }
+            }
+        });
 
         tokens.extend(quote! {
-            PciClassEntry { id: #id, name: #name, subclasses: &[#(#subclasses),*] }
+            PciClassEntry { id: #id, name: #name, subclasses: Entries::Slice(&[#(#subclasses),*]) }
         });
     }
 }
 
-impl quote::ToTokens for PciProgEntry {
+impl quote::ToTokens for ParsedProg {
     fn to_tokens(&self, tokens: &mut TokenStream) {
-        let PciProgEntry { id, name } = self;
+        let ParsedProg { id, name } = self;
 
         tokens.extend(quote! {
             PciProgEntry { id: #id, name: #name }
@@ -4,23 +4,25 @@
 //! # lspci
 //! This `lspci` is a Rust reimplementation of the `lspci` from `libpci`,
 //! using the `libpci-rs` backend. It offers a limited subset of the
-//! functionality from the original `lspci`. See the [args](crate::Args) 
+//! functionality from the original `lspci`. See the [args](crate::Args)
 //! section for usage information.
 
 use std::collections::BTreeMap;
 
-use clap::{command, Parser};
+use clap::Parser;
+use libpci_rs::machine::{MachineDetails, MachineFormat, MachineFormatter};
+use libpci_rs::names::{NameFormatter, NameStyle};
 use libpci_rs::pci::*;
 
 #[derive(Parser)]
 #[command(version, about = "A reimplementation of lspci using libpci-rs.", long_about = None)]
 struct Args {
-    /// Verbosity (`-v`, `--verbose`): Increases the amount of supplementary 
+    /// Verbosity (`-v`, `--verbose`): Increases the amount of supplementary
     /// info printed. Use multiple flags for more info.
     #[arg(short, long, help = "Verbosity (use more than once for more details)", action = clap::ArgAction::Count)]
     verbose: u8,
     /// Numeracy (`-n`, `--numeric`): Change the format of the output to use
-    /// just numbers, or numbers and text. Use more flags for different 
+    /// just numbers, or numbers and text. Use more flags for different
     /// formats.
     #[arg(short, long, help = "Numeracy (use more than once for different options)", action = clap::ArgAction::Count)]
     numeric: u8,
@@ -28,6 +30,15 @@ struct Args {
     /// device with an obtainable address.
     #[arg(short, long, help = "Display a tree view")]
     tree: bool,
+    /// Machine-readable (`-m`): Display the devices in the machine-readable
+    /// format of pciutils. Use `-mm` for the modern format, and combine with
+    /// `-v` for a record of tagged lines per device.
+    #[arg(short = 'm', help = "Machine-readable output (use twice for the modern format)", action = clap::ArgAction::Count)]
+    machine: u8,
+    /// Kernel (`-k`): Show the kernel driver in use and the kernel modules
+    /// able to drive each device. Only available on Linux and Android.
+    #[arg(short = 'k', help = "Show kernel drivers and modules")]
+    kernel: bool,
 }
 
 // Tree
@@ -170,110 +181,42 @@ fn print_tree(tree: PciDeviceTree) {
     }
 }
 
-// Numeric level zero.
-// 0000:00:00.0 Subclassname [classsubclass]: Vendor Devicename (rev 01)
-fn numeracy_0(device: PciDeviceHardware) -> String {
-    format!(
-        "{} {}: {} {} {}",
+// The first line printed for each device, with names styled according to the
+// numeric flag.
+// 0000:00:00.0 Subclassname: Vendor Devicename (rev 01)
+// 0000:00:00.0 classsubclass: vid:did (rev 01)
+// 0000:00:00.0 Subclassname [classsubclass]: Vendor Devicename [vid:did] (rev 01)
+fn device_line(device: &PciDeviceHardware, style: NameStyle) -> String {
+    let names = NameFormatter::new(device, style);
+    let mut line = format!(
+        "{} {}: {}",
         if let Some(addr) = device.address {
-            format!(
-                "{:04x}:{:02x}:{:02x}.{:01x}",
-                addr.domain, addr.bus, addr.device, addr.function
-            )
+            addr.to_string()
         } else {
             "<address unavailable>".to_string()
         },
-        device
-            .subclass_name()
-            .unwrap_or(device.class_name().unwrap_or(format!(
-                "<unknown class {:02x}{:02x}>",
-                device.class, device.subclass
-            ))),
-        device
-            .vendor_name()
-            .unwrap_or("<unknown vendor>".to_string()),
-        device
-            .subdevice_name()
-            .unwrap_or(device.device_name().unwrap_or(format!(
-                "<unknown device {:04x}:{:04x}>",
-                device.vendor_id, device.device_id
-            ))),
-        if device.revision_id != 0 {
-            format!("(rev {:02x})", device.revision_id)
-        } else {
-            "".to_string()
-        }
-    )
-    .trim()
-    .to_string()
+        names.class().unwrap_or_default(),
+        names.vendor_device().unwrap_or_default(),
+    );
+    if device.revision_id != 0 {
+        line.push_str(&format!(" (rev {:02x})", device.revision_id));
+    }
+    line
 }
 
-// Numeric level one.
-// 0000:00:00.0 classcode: vid:did (rev 01)
-fn numeracy_1(device: PciDeviceHardware) -> String {
-    format!(
-        "{} {:02x}{:02x}: {:04x}:{:04x} {}",
-        if let Some(addr) = device.address {
-            format!(
-                "{:04x}:{:02x}:{:02x}.{:01x}",
-                addr.domain, addr.bus, addr.device, addr.function
-            )
-        } else {
-            "<address unavailable>".to_string()
-        },
-        device.class,
-        device.subclass,
-        device.vendor_id,
-        device.device_id,
-        if device.revision_id != 0 {
-            format!("(rev {:02x})", device.revision_id)
-        } else {
-            "".to_string()
-        }
-    )
-    .trim()
-    .to_string()
+// The details shown by the verbose machine-readable format, which only Linux
+// and Android expose.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn machine_details(device: &PciDeviceHardware, kernel: bool) -> MachineDetails {
+    match device.address {
+        Some(address) => MachineDetails::read(&address, kernel),
+        None => MachineDetails::default(),
+    }
 }
-// Numeric level two.
-// 0000:00:00.0 Subclassname [classsubclass]: Vendor Devicename (rev 01)
-fn numeracy_2(device: PciDeviceHardware) -> String {
-    format!(
-        "{} {} [{:02x}{:02x}]: {} {} [{:04x}:{:04x}] {}",
-        if let Some(addr) = device.address {
-            format!(
-                "{:04x}:{:02x}:{:02x}.{:01x}",
-                addr.domain, addr.bus, addr.device, addr.function
-            )
-        } else {
-            "<address unavailable>".to_string()
-        },
-        device
-            .subclass_name()
-            .unwrap_or(device.class_name().unwrap_or(format!(
-                "<unknown class {:02x}{:02x}>",
-                device.class, device.subclass
-            ))),
-        device.class,
-        device.subclass,
-        device
-            .vendor_name()
-            .unwrap_or("<unknown vendor>".to_string()),
-        device
-            .subdevice_name()
-            .unwrap_or(device.device_name().unwrap_or(format!(
-                "<unknown device {:04x}:{:04x}>",
-                device.vendor_id, device.device_id
-            ))),
-        device.vendor_id,
-        device.device_id,
-        if device.revision_id != 0 {
-            format!("(rev {:02x})", device.revision_id)
-        } else {
-            "".to_string()
-        }
-    )
-    .trim()
-    .to_string()
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn machine_details(_device: &PciDeviceHardware, _kernel: bool) -> MachineDetails {
+    MachineDetails::default()
 }
 
 // Verbosity 0 does not exist, since it won't print anything in the second
@@ -291,11 +234,11 @@ fn main() {
     if let Ok(mut devices) = pci_list {
         devices.sort();
 
-        // Depending on our arg, we choose a formatter
-        let line1_formatter: fn(PciDeviceHardware) -> String = match args.numeric {
-            0 => numeracy_0,
-            1 => numeracy_1,
-            2.. => numeracy_2,
+        // Depending on our arg, we choose how names are shown.
+        let style = match args.numeric {
+            0 => NameStyle::Names,
+            1 => NameStyle::Numeric,
+            2.. => NameStyle::Mixed,
         };
 
         //let line2_formatter: fn(PciDeviceHardware) -> String = match args.verbose {
@@ -314,9 +257,35 @@ fn main() {
                 let device_tree: PciDeviceTree = tree_from_vec(devices);
                 print_tree(device_tree);
             }
+            false if args.machine > 0 => {
+                let formatter = MachineFormatter {
+                    format: match args.machine {
+                        1 => MachineFormat::Compatible,
+                        _ => MachineFormat::Modern,
+                    },
+                    verbose: args.verbose > 0,
+                    style,
+                };
+                for device in devices {
+                    let details = match formatter.verbose {
+                        true => machine_details(&device, args.kernel),
+                        false => MachineDetails::default(),
+                    };
+                    print!("{}", formatter.format(&device, &details));
+                }
+            }
             false => {
                 for device in devices {
-                    println!("{}", line1_formatter(device));
+                    println!("{}", device_line(&device, style));
+                    if args.kernel {
+                        let details = machine_details(&device, true);
+                        if let Some(driver) = details.driver {
+                            println!("\tKernel driver in use: {}", driver);
+                        }
+                        if !details.modules.is_empty() {
+                            println!("\tKernel modules: {}", details.modules.join(", "));
+                        }
+                    }
                 }
             }
         }
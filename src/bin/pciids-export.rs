@@ -0,0 +1,83 @@
+// Copyright (c) 2024 Gibson Pilconis, shibedrill, and contributors
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! # pciids-export
+//! `pciids-export` writes the PCI IDs database used by `libpci-rs` out as
+//! JSON, CSV or SQL, so that other tools can show the same names. See the
+//! [args](crate::Args) section for usage information, and
+//! [`libpci_rs::ids::export`] for the formats.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::{Parser, ValueEnum};
+use libpci_rs::class::all_classes;
+use libpci_rs::ids::export::{export, ExportFormat};
+use libpci_rs::ids::{all_vendors, PciIdsDatabase};
+
+#[derive(Parser)]
+#[command(version, about = "Exports the PCI IDs database used by libpci-rs.", long_about = None)]
+struct Args {
+    /// Format (`-f`, `--format`): The format to write the database in.
+    #[arg(short, long, help = "Output format", value_enum, default_value_t = Format::Json)]
+    format: Format,
+    /// IDs (`-i`, `--ids`): Export a pci.ids file instead of the database
+    /// compiled into the library.
+    #[arg(
+        short,
+        long,
+        help = "Export a pci.ids file instead of the built-in database"
+    )]
+    ids: Option<PathBuf>,
+    /// Output (`-o`, `--output`): Write to a file instead of standard output.
+    #[arg(short, long, help = "Write to a file instead of standard output")]
+    output: Option<PathBuf>,
+}
+
+#[derive(Copy, Clone, ValueEnum)]
+enum Format {
+    Json,
+    Csv,
+    Sql,
+}
+
+impl From<Format> for ExportFormat {
+    fn from(format: Format) -> Self {
+        match format {
+            Format::Json => ExportFormat::Json,
+            Format::Csv => ExportFormat::Csv,
+            Format::Sql => ExportFormat::Sql,
+        }
+    }
+}
+
+fn run(args: Args) -> Result<(), Box<dyn std::error::Error>> {
+    let writer: Box<dyn Write> = match args.output {
+        Some(path) => Box::new(File::create(path)?),
+        None => Box::new(io::stdout().lock()),
+    };
+    let writer = BufWriter::new(writer);
+    let format = args.format.into();
+
+    match args.ids {
+        Some(path) => {
+            let database = PciIdsDatabase::load(path)?;
+            export(writer, format, database.vendors(), database.classes())?;
+        }
+        // The same view lookups get, including any ID overrides.
+        None => export(writer, format, all_vendors(), all_classes())?,
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    match run(Args::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("Error exporting PCI IDs database: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
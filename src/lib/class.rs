@@ -8,7 +8,7 @@
 //! ```rust
 //! # use libpci_rs::class::{PciClassEntry, PciSubclassEntry, lookup_class};
 //! let class_entry: PciClassEntry = lookup_class(0x03).unwrap();
-//! let subclass_entry: &PciSubclassEntry = class_entry.subclass(0x00).unwrap();
+//! let subclass_entry: PciSubclassEntry = class_entry.subclass(0x00).unwrap();
 //! assert_eq!("VGA compatible controller", subclass_entry.name().to_owned());
 //! ```
 //! Or by calling the gated methods of `PciDeviceHardware`:
@@ -21,47 +21,119 @@
 
 #![allow(dead_code)]
 
+#[cfg(feature = "pciids-compact")]
+pub(crate) use crate::ids::compact::{builtin_class, builtin_class_ids};
+use crate::ids::parser::ParsedClass;
+use crate::ids::{layer_databases, leak_str, lookup_layered, merge_entries, ChildEntry, Entries};
+
+use std::collections::BTreeSet;
+
 include!(concat!(env!("OUT_DIR"), "/pci_classes_phf.rs"));
 
 /// An ID entry representing a PCI device class.
 #[derive(Copy, Clone)]
 pub struct PciClassEntry {
     /// The numeric ID of the class.
-    id: u8,
+    pub(crate) id: u8,
     /// The written name of the class.
-    name: &'static str,
+    pub(crate) name: &'static str,
     /// The list of subclasses the class has.
-    subclasses: &'static [PciSubclassEntry],
+    pub(crate) subclasses: Entries<PciSubclassEntry>,
 }
 
 /// An ID entry representing a PCI device subclass.
 #[derive(Copy, Clone)]
 pub struct PciSubclassEntry {
     /// The numeric ID of the subclass.
-    id: u8,
+    pub(crate) id: u8,
     /// The written name of the subclass.
-    name: &'static str,
+    pub(crate) name: &'static str,
     /// The list of programming interfaces the subclass has.
-    progs: &'static [PciProgEntry],
+    pub(crate) progs: Entries<PciProgEntry>,
 }
 
 /// An ID entry representing a PCI device programming interface.
 #[derive(Copy, Clone)]
 pub struct PciProgEntry {
     /// The numeric ID of the programming interface.
-    id: u8,
+    pub(crate) id: u8,
     /// The written name of the programming interface.
-    name: &'static str,
+    pub(crate) name: &'static str,
 }
 
-/// Parses an integer ID to a `PciClassEntry`, if one with the ID exists.
+/// Parses an integer ID to a `PciClassEntry`, if one with the ID exists. The
+/// [ID overrides](crate::ids::set_id_overrides) and the
+/// [runtime database](crate::ids::set_runtime_database) are consulted first,
+/// if set.
 pub fn lookup_class(id: u8) -> Option<PciClassEntry> {
+    lookup_layered(|x| x.class(id)).or_else(|| builtin_class(id))
+}
+
+/// Iterates over every class, sorted by ID. Classes from the
+/// [ID overrides](crate::ids::set_id_overrides) and the
+/// [runtime database](crate::ids::set_runtime_database) are included, and
+/// take precedence over compiled-in ones just like with [`lookup_class`].
+pub fn all_classes() -> impl Iterator<Item = PciClassEntry> {
+    let mut ids: BTreeSet<u8> = builtin_class_ids().collect();
+    for database in layer_databases() {
+        ids.extend(database.classes().map(|x| x.id));
+    }
+    ids.into_iter().filter_map(lookup_class)
+}
+
+/// Returns the number of classes [`all_classes`] iterates over.
+pub fn class_count() -> usize {
+    all_classes().count()
+}
+
+#[cfg(not(feature = "pciids-compact"))]
+pub(crate) fn builtin_class(id: u8) -> Option<PciClassEntry> {
     let result = CLASSES.get(&id);
-    result?;
-    Some(*result.unwrap())
+    Some(*result?)
+}
+
+#[cfg(not(feature = "pciids-compact"))]
+fn builtin_class_ids() -> impl Iterator<Item = u8> {
+    CLASSES.keys().copied()
 }
 
 impl PciClassEntry {
+    pub(crate) fn leak(parsed: ParsedClass) -> Self {
+        let subclasses: Vec<PciSubclassEntry> = parsed
+            .subclasses
+            .into_iter()
+            .map(|subclass| {
+                let progs: Vec<PciProgEntry> = subclass
+                    .progs
+                    .into_iter()
+                    .map(|prog| PciProgEntry {
+                        id: prog.id,
+                        name: leak_str(prog.name),
+                    })
+                    .collect();
+                PciSubclassEntry {
+                    id: subclass.id,
+                    name: leak_str(subclass.name),
+                    progs: Entries::Slice(progs.leak()),
+                }
+            })
+            .collect();
+
+        PciClassEntry {
+            id: parsed.id,
+            name: leak_str(parsed.name),
+            subclasses: Entries::Slice(subclasses.leak()),
+        }
+    }
+
+    pub(crate) fn merge(base: Self, overlay: Self) -> Self {
+        PciClassEntry {
+            id: overlay.id,
+            name: overlay.name,
+            subclasses: merge_entries(base.subclasses, overlay.subclasses, PciSubclassEntry::merge),
+        }
+    }
+
     /// Gets the ID of the class.
     pub fn id(&self) -> u8 {
         self.id
@@ -72,22 +144,26 @@ impl PciClassEntry {
         self.name
     }
 
-    /// Gets all the subclasses associated with a class.
-    pub fn subclasses(&self) -> Option<Vec<&PciSubclassEntry>> {
-        let ret: Vec<&PciSubclassEntry> = self.subclasses.iter().collect();
-        match ret.is_empty() {
-            true => None,
-            false => Some(ret),
-        }
+    /// Gets all the subclasses associated with a class, sorted by ID.
+    pub fn subclasses(&self) -> Option<Vec<PciSubclassEntry>> {
+        self.subclasses.to_vec()
     }
 
     /// Gets a subclass associated with a class by its ID.
-    pub fn subclass(&self, _id: u8) -> Option<&PciSubclassEntry> {
-        self.subclasses.iter().find(|x| x.id == _id)
+    pub fn subclass(&self, _id: u8) -> Option<PciSubclassEntry> {
+        self.subclasses.find(_id)
     }
 }
 
 impl PciSubclassEntry {
+    fn merge(base: Self, overlay: Self) -> Self {
+        PciSubclassEntry {
+            id: overlay.id,
+            name: overlay.name,
+            progs: merge_entries(base.progs, overlay.progs, |_, x| x),
+        }
+    }
+
     /// Gets the ID of the subclass.
     pub fn id(&self) -> u8 {
         self.id
@@ -98,18 +174,14 @@ impl PciSubclassEntry {
         self.name
     }
 
-    /// Gets all the progs associated with a subclass.
-    pub fn progs(&self) -> Option<Vec<&PciProgEntry>> {
-        let ret: Vec<&PciProgEntry> = self.progs.iter().collect();
-        match ret.is_empty() {
-            true => None,
-            false => Some(ret),
-        }
+    /// Gets all the progs associated with a subclass, sorted by ID.
+    pub fn progs(&self) -> Option<Vec<PciProgEntry>> {
+        self.progs.to_vec()
     }
 
     /// Gets a prog associated with a subclass by its ID.
-    pub fn prog(&self, _id: u8) -> Option<&PciProgEntry> {
-        self.progs.iter().find(|x| x.id == _id)
+    pub fn prog(&self, _id: u8) -> Option<PciProgEntry> {
+        self.progs.find(_id)
     }
 }
 
@@ -125,9 +197,35 @@ impl PciProgEntry {
     }
 }
 
+impl ChildEntry for PciSubclassEntry {
+    type Key = u8;
+
+    fn key(&self) -> u8 {
+        self.id
+    }
+
+    #[cfg(feature = "pciids-compact")]
+    fn decode(index: usize) -> Self {
+        crate::ids::compact::decode_subclass(index)
+    }
+}
+
+impl ChildEntry for PciProgEntry {
+    type Key = u8;
+
+    fn key(&self) -> u8 {
+        self.id
+    }
+
+    #[cfg(feature = "pciids-compact")]
+    fn decode(index: usize) -> Self {
+        crate::ids::compact::decode_prog(index)
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::class::lookup_class;
+    use crate::class::{all_classes, class_count, lookup_class};
 
     /// Test looking up a specific device class.
     #[test]
@@ -135,4 +233,13 @@ mod tests {
         let class = lookup_class(9).unwrap();
         assert_eq!(class.name(), "Input device controller");
     }
+
+    /// Test iterating over every class.
+    #[test]
+    fn test_all_classes() {
+        let ids: Vec<u8> = all_classes().map(|x| x.id()).collect();
+        assert!(ids.windows(2).all(|x| x[0] < x[1]));
+        assert!(ids.contains(&0x09));
+        assert_eq!(ids.len(), class_count());
+    }
 }
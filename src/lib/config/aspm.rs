@@ -0,0 +1,121 @@
+// Copyright (c) 2024 Gibson Pilconis, shibedrill, and contributors
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! # About this module
+//! Active State Power Management lets a PCI Express link drop into low power
+//! states while it is idle. L0s and L1 are advertised in the Link
+//! Capabilities register and enabled in Link Control, while the deeper L1.1
+//! and L1.2 substates live in the L1 PM Substates extended capability:
+//! ```rust
+//! # use libpci_rs::pci::get_pci_list;
+//! for device in get_pci_list().unwrap() {
+//!     let Some(address) = device.address else { continue };
+//!     let Ok(config) = address.config_space() else { continue };
+//!     if let Some(aspm) = config.aspm() {
+//!         println!("{}: supported {:?}, enabled {:?}", address, aspm.supported, aspm.enabled);
+//!     }
+//! }
+//! ```
+
+use crate::config::{PciConfigSpace, PCI_CAP_ID_EXP, PCI_EXT_CAP_ID_L1SS};
+
+/// A set of ASPM link states.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct PciAspmStates {
+    /// The L0s state.
+    pub l0s: bool,
+    /// The L1 state.
+    pub l1: bool,
+    /// The L1.1 substate.
+    pub l1_1: bool,
+    /// The L1.2 substate.
+    pub l1_2: bool,
+}
+
+/// The ASPM states of a PCI Express link, as seen from one of its ends.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct PciAspm {
+    /// The states the device supports.
+    pub supported: PciAspmStates,
+    /// The states currently enabled on the link.
+    pub enabled: PciAspmStates,
+}
+
+impl PciConfigSpace {
+    /// Decodes the ASPM support and state of the link, if the device is a
+    /// PCI Express device. The L1 substates are only reported if the full
+    /// extended configuration space is available, and are left unset if the
+    /// L1 PM Substates capability can't be read.
+    pub fn aspm(&self) -> Option<PciAspm> {
+        let base = self.capability(PCI_CAP_ID_EXP)?.offset as usize;
+        let link_capabilities = self.read_u32(base + 0x0c)?;
+        let link_control = self.read_u16(base + 0x10)?;
+
+        let mut aspm = PciAspm {
+            supported: PciAspmStates {
+                l0s: link_capabilities & 0x0400 != 0,
+                l1: link_capabilities & 0x0800 != 0,
+                ..Default::default()
+            },
+            enabled: PciAspmStates {
+                l0s: link_control & 0x1 != 0,
+                l1: link_control & 0x2 != 0,
+                ..Default::default()
+            },
+        };
+
+        let l1ss = self.extended_capability(PCI_EXT_CAP_ID_L1SS).and_then(|x| {
+            let base = x.offset as usize;
+            Some((self.read_u32(base + 4)?, self.read_u32(base + 8)?))
+        });
+        if let Some((capabilities, control)) = l1ss {
+            aspm.supported.l1_2 = capabilities & 0x4 != 0;
+            aspm.supported.l1_1 = capabilities & 0x8 != 0;
+            aspm.enabled.l1_2 = control & 0x4 != 0;
+            aspm.enabled.l1_1 = control & 0x8 != 0;
+        }
+
+        Some(aspm)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::tests::config_with_capabilities;
+
+    /// Test decoding ASPM from the PCI Express and L1 PM Substates
+    /// capabilities.
+    #[test]
+    fn test_aspm() {
+        let mut body = vec![0u8; 0x14];
+        body[0] = PCI_CAP_ID_EXP;
+        // Link Capabilities: L0s and L1 supported.
+        body[0x0c..0x10].copy_from_slice(&0x0000_0c00u32.to_le_bytes());
+        // Link Control: L1 enabled.
+        body[0x10..0x12].copy_from_slice(&0x0002u16.to_le_bytes());
+        let mut data = config_with_capabilities(&[(0x70, &body)])
+            .as_bytes()
+            .to_vec();
+
+        let config = PciConfigSpace::from(data.clone());
+        let aspm = config.aspm().unwrap();
+        assert!(aspm.supported.l0s && aspm.supported.l1);
+        assert!(!aspm.enabled.l0s && aspm.enabled.l1);
+        assert!(!aspm.supported.l1_1 && !aspm.supported.l1_2);
+
+        // L1 PM Substates: both ASPM substates supported, only L1.1 enabled.
+        data[0x100..0x104].copy_from_slice(&0x0001_001eu32.to_le_bytes());
+        data[0x104..0x108].copy_from_slice(&0x0000_001fu32.to_le_bytes());
+        data[0x108..0x10c].copy_from_slice(&0x0000_0008u32.to_le_bytes());
+        let aspm = PciConfigSpace::from(data.clone()).aspm().unwrap();
+        assert!(aspm.supported.l1_1 && aspm.supported.l1_2);
+        assert!(aspm.enabled.l1_1 && !aspm.enabled.l1_2);
+
+        // A truncated L1 PM Substates capability only hides the substates.
+        data.truncate(0x106);
+        let aspm = PciConfigSpace::from(data).aspm().unwrap();
+        assert!(aspm.supported.l0s && aspm.supported.l1 && aspm.enabled.l1);
+        assert!(!aspm.supported.l1_1 && !aspm.supported.l1_2);
+    }
+}
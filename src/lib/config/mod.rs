@@ -0,0 +1,255 @@
+// Copyright (c) 2024 Gibson Pilconis, shibedrill, and contributors
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! # About this module
+//! Every PCI function has a configuration space: 256 bytes for conventional
+//! PCI, or 4096 bytes for PCI Express. Besides the IDs that
+//! [PciDeviceHardware](crate::pci::PciDeviceHardware) already holds, it
+//! contains a linked list of capabilities describing optional features like
+//! interrupts, power management, and link state. This module decodes them
+//! from a raw copy of the configuration space, so it works the same no
+//! matter where the bytes came from:
+//! ```rust
+//! # use libpci_rs::config::{PciConfigSpace, PCI_CAP_ID_MSI};
+//! let mut bytes = vec![0u8; 256];
+//! bytes[0x06] = 0x10; // Status: capability list present.
+//! bytes[0x34] = 0x40; // Capabilities pointer.
+//! bytes[0x40] = PCI_CAP_ID_MSI;
+//! let config = PciConfigSpace::from(bytes);
+//! assert_eq!(config.capability(PCI_CAP_ID_MSI).unwrap().offset, 0x40);
+//! ```
+//! On Linux, you can read the configuration space of a device with
+//! [`PciDeviceAddress::config_space`](crate::pci::PciDeviceAddress::config_space).
+
+/// Active State Power Management decoding.
+pub mod aspm;
+/// MSI and MSI-X capability decoding.
+pub mod msi;
+/// Power Management capability decoding.
+pub mod power;
+/// Function Level Reset support decoding.
+pub mod reset;
+
+/// Capability ID of the Power Management capability.
+pub const PCI_CAP_ID_PM: u8 = 0x01;
+/// Capability ID of the Message Signaled Interrupts capability.
+pub const PCI_CAP_ID_MSI: u8 = 0x05;
+/// Capability ID of the PCI Express capability.
+pub const PCI_CAP_ID_EXP: u8 = 0x10;
+/// Capability ID of the MSI-X capability.
+pub const PCI_CAP_ID_MSIX: u8 = 0x11;
+/// Capability ID of the Advanced Features capability.
+pub const PCI_CAP_ID_AF: u8 = 0x13;
+
+/// Extended capability ID of the L1 PM Substates capability.
+pub const PCI_EXT_CAP_ID_L1SS: u16 = 0x1e;
+
+/// Offset of the status register in the configuration header.
+const PCI_STATUS: usize = 0x06;
+/// Status bit signalling that the capability list is present.
+const PCI_STATUS_CAP_LIST: u16 = 0x10;
+/// Offset of the capabilities pointer in the configuration header.
+const PCI_CAPABILITY_LIST: usize = 0x34;
+/// Offset of the first extended capability, right after the conventional
+/// configuration space.
+const PCI_EXT_CAP_START: usize = 0x100;
+
+/// A copy of the configuration space of a PCI function.
+///
+/// The copy may be shorter than the full configuration space. Most operating
+/// systems only let unprivileged users read the first 64 bytes, in which case
+/// no capabilities can be found.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PciConfigSpace {
+    data: Vec<u8>,
+}
+
+/// A capability in the conventional capability list.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct PciCapability {
+    /// The capability ID.
+    pub id: u8,
+    /// The offset of the capability in the configuration space.
+    pub offset: u8,
+}
+
+/// A capability in the PCI Express extended capability list.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct PciExtendedCapability {
+    /// The extended capability ID.
+    pub id: u16,
+    /// The version of the capability structure.
+    pub version: u8,
+    /// The offset of the capability in the configuration space.
+    pub offset: u16,
+}
+
+impl From<Vec<u8>> for PciConfigSpace {
+    fn from(data: Vec<u8>) -> Self {
+        PciConfigSpace { data }
+    }
+}
+
+impl PciConfigSpace {
+    /// Returns the raw bytes of the configuration space.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Returns how many bytes of the configuration space are available.
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Returns true if no bytes of the configuration space are available.
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Reads a byte at an offset, if it is available.
+    pub fn read_u8(&self, offset: usize) -> Option<u8> {
+        self.data.get(offset).copied()
+    }
+
+    /// Reads a little-endian word at an offset, if it is available.
+    pub fn read_u16(&self, offset: usize) -> Option<u16> {
+        let bytes = self.data.get(offset..offset + 2)?;
+        Some(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    /// Reads a little-endian double word at an offset, if it is available.
+    pub fn read_u32(&self, offset: usize) -> Option<u32> {
+        let bytes = self.data.get(offset..offset + 4)?;
+        Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    /// Gets all the capabilities in the conventional capability list, in the
+    /// order the device links them.
+    pub fn capabilities(&self) -> Vec<PciCapability> {
+        let mut result: Vec<PciCapability> = Vec::new();
+
+        if self.read_u16(PCI_STATUS).unwrap_or(0) & PCI_STATUS_CAP_LIST == 0 {
+            return result;
+        }
+
+        let mut offset = self.read_u8(PCI_CAPABILITY_LIST).unwrap_or(0) & !0x3;
+        // Broken devices can link the list into a loop, so there can't be
+        // more capabilities than fit into the header.
+        while offset >= 0x40 && result.len() < 48 {
+            let (Some(id), Some(next)) = (
+                self.read_u8(offset as usize),
+                self.read_u8(offset as usize + 1),
+            ) else {
+                break;
+            };
+            result.push(PciCapability { id, offset });
+            offset = next & !0x3;
+        }
+
+        result
+    }
+
+    /// Gets the first capability with a given ID, if the device has it.
+    pub fn capability(&self, id: u8) -> Option<PciCapability> {
+        self.capabilities().into_iter().find(|x| x.id == id)
+    }
+
+    /// Gets all the capabilities in the PCI Express extended capability list.
+    /// This is empty unless the full 4096 bytes were read.
+    pub fn extended_capabilities(&self) -> Vec<PciExtendedCapability> {
+        let mut result: Vec<PciExtendedCapability> = Vec::new();
+
+        let mut offset = PCI_EXT_CAP_START;
+        while offset >= PCI_EXT_CAP_START && result.len() < 1024 {
+            let Some(header) = self.read_u32(offset) else {
+                break;
+            };
+            // An empty header means there are no extended capabilities.
+            if header == 0 || header == 0xffffffff {
+                break;
+            }
+            result.push(PciExtendedCapability {
+                id: (header & 0xffff) as u16,
+                version: ((header >> 16) & 0xf) as u8,
+                offset: offset as u16,
+            });
+            offset = ((header >> 20) & 0xffc) as usize;
+        }
+
+        result
+    }
+
+    /// Gets the first extended capability with a given ID, if the device has
+    /// it.
+    pub fn extended_capability(&self, id: u16) -> Option<PciExtendedCapability> {
+        self.extended_capabilities()
+            .into_iter()
+            .find(|x| x.id == id)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Builds a configuration space with the given capabilities linked in
+    /// order. Each capability is given as its offset and body, starting with
+    /// the capability ID.
+    pub(crate) fn config_with_capabilities(capabilities: &[(u8, &[u8])]) -> PciConfigSpace {
+        let mut data = vec![0u8; 4096];
+        data[PCI_STATUS] = PCI_STATUS_CAP_LIST as u8;
+        data[PCI_CAPABILITY_LIST] = capabilities.first().map(|x| x.0).unwrap_or(0);
+
+        for (index, (offset, body)) in capabilities.iter().enumerate() {
+            let offset = *offset as usize;
+            data[offset..offset + body.len()].copy_from_slice(body);
+            data[offset + 1] = capabilities.get(index + 1).map(|x| x.0).unwrap_or(0);
+        }
+
+        PciConfigSpace::from(data)
+    }
+
+    /// Test walking the conventional capability list.
+    #[test]
+    fn test_capabilities() {
+        let config = config_with_capabilities(&[
+            (0x40, &[PCI_CAP_ID_PM, 0, 0, 0]),
+            (0x50, &[PCI_CAP_ID_MSI, 0, 0, 0]),
+            (0x70, &[PCI_CAP_ID_EXP, 0, 0, 0]),
+        ]);
+        let ids: Vec<u8> = config.capabilities().iter().map(|x| x.id).collect();
+        assert_eq!(ids, vec![PCI_CAP_ID_PM, PCI_CAP_ID_MSI, PCI_CAP_ID_EXP]);
+        assert_eq!(config.capability(PCI_CAP_ID_EXP).unwrap().offset, 0x70);
+        assert!(config.capability(PCI_CAP_ID_MSIX).is_none());
+
+        // A truncated copy of the configuration space has no capabilities.
+        let truncated = PciConfigSpace::from(config.as_bytes()[..64].to_vec());
+        assert!(truncated.capabilities().is_empty());
+    }
+
+    /// Test walking a capability list that loops back on itself.
+    #[test]
+    fn test_capability_loop() {
+        let mut data = config_with_capabilities(&[(0x40, &[PCI_CAP_ID_PM, 0])]).data;
+        data[0x41] = 0x40;
+        let config = PciConfigSpace::from(data);
+        assert_eq!(config.capabilities().len(), 48);
+    }
+
+    /// Test walking the extended capability list.
+    #[test]
+    fn test_extended_capabilities() {
+        let mut data = vec![0u8; 4096];
+        // AER at 0x100 linking to L1 PM Substates at 0x150.
+        data[0x100..0x104].copy_from_slice(&0x1501_0001u32.to_le_bytes());
+        data[0x150..0x154].copy_from_slice(&0x0001_001eu32.to_le_bytes());
+        let config = PciConfigSpace::from(data);
+        let ids: Vec<u16> = config
+            .extended_capabilities()
+            .iter()
+            .map(|x| x.id)
+            .collect();
+        assert_eq!(ids, vec![0x0001, 0x001e]);
+        assert_eq!(config.extended_capability(0x001e).unwrap().offset, 0x150);
+    }
+}
@@ -0,0 +1,239 @@
+// Copyright (c) 2024 Gibson Pilconis, shibedrill, and contributors
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! # About this module
+//! Message Signaled Interrupts replace the legacy interrupt pins with memory
+//! writes. MSI gives a device up to 32 vectors sharing one address, while
+//! MSI-X gives it up to 2048 vectors, each with its own address and data,
+//! stored in a table inside one of its BARs. This module decodes both
+//! capabilities, along with the entries of an MSI-X table:
+//! ```rust
+//! # use libpci_rs::pci::get_pci_list;
+//! for device in get_pci_list().unwrap() {
+//!     let Some(address) = device.address else { continue };
+//!     let Ok(config) = address.config_space() else { continue };
+//!     if let Some(msix) = config.msix() {
+//!         println!("{}: {} MSI-X vectors in BAR {}", address, msix.table_size, msix.table_bir);
+//!     }
+//! }
+//! ```
+
+use crate::config::{PciConfigSpace, PCI_CAP_ID_MSI, PCI_CAP_ID_MSIX};
+
+/// The size of an entry in the MSI-X table, in bytes.
+pub const PCI_MSIX_ENTRY_SIZE: usize = 16;
+
+/// A decoded MSI capability.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PciMsiCapability {
+    /// Whether MSI is enabled.
+    pub enabled: bool,
+    /// The number of vectors the device can use.
+    pub multiple_message_capable: u8,
+    /// The number of vectors software allocated to the device.
+    pub multiple_message_enabled: u8,
+    /// Whether the device can write to a 64-bit message address.
+    pub is_64bit: bool,
+    /// Whether the device can mask individual vectors.
+    pub per_vector_masking: bool,
+    /// The programmed message address.
+    pub address: u64,
+    /// The programmed message data.
+    pub data: u16,
+    /// The mask bits of each vector, if the device supports per-vector
+    /// masking.
+    pub mask: Option<u32>,
+    /// The pending bits of each vector, if the device supports per-vector
+    /// masking.
+    pub pending: Option<u32>,
+}
+
+/// A decoded MSI-X capability.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PciMsiXCapability {
+    /// Whether MSI-X is enabled.
+    pub enabled: bool,
+    /// Whether all vectors of the function are masked.
+    pub function_mask: bool,
+    /// The number of entries in the MSI-X table.
+    pub table_size: u16,
+    /// The BAR the MSI-X table is in.
+    pub table_bir: u8,
+    /// The offset of the MSI-X table inside its BAR.
+    pub table_offset: u32,
+    /// The BAR the Pending Bit Array is in.
+    pub pba_bir: u8,
+    /// The offset of the Pending Bit Array inside its BAR.
+    pub pba_offset: u32,
+}
+
+/// An entry of the MSI-X table.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PciMsiXEntry {
+    /// The programmed message address.
+    pub address: u64,
+    /// The programmed message data.
+    pub data: u32,
+    /// Whether the vector is masked.
+    pub masked: bool,
+}
+
+impl PciMsiXEntry {
+    /// Decodes an entry from the 16 bytes it occupies in the MSI-X table.
+    pub fn from_bytes(bytes: &[u8; PCI_MSIX_ENTRY_SIZE]) -> Self {
+        let dword = |index: usize| {
+            u32::from_le_bytes([
+                bytes[index * 4],
+                bytes[index * 4 + 1],
+                bytes[index * 4 + 2],
+                bytes[index * 4 + 3],
+            ])
+        };
+
+        PciMsiXEntry {
+            address: dword(0) as u64 | ((dword(1) as u64) << 32),
+            data: dword(2),
+            masked: dword(3) & 0x1 != 0,
+        }
+    }
+
+    /// Decodes all the entries of an MSI-X table. Trailing bytes that don't
+    /// make up a whole entry are ignored.
+    pub fn from_table(table: &[u8]) -> Vec<Self> {
+        table
+            .chunks_exact(PCI_MSIX_ENTRY_SIZE)
+            .map(|x| Self::from_bytes(x.try_into().unwrap()))
+            .collect()
+    }
+}
+
+impl PciConfigSpace {
+    /// Decodes the MSI capability, if the device has one.
+    pub fn msi(&self) -> Option<PciMsiCapability> {
+        let base = self.capability(PCI_CAP_ID_MSI)?.offset as usize;
+        let control = self.read_u16(base + 2)?;
+
+        let is_64bit = control & 0x0080 != 0;
+        let per_vector_masking = control & 0x0100 != 0;
+
+        let address_low = self.read_u32(base + 4)? as u64;
+        let (address, data_offset) = if is_64bit {
+            let address_high = self.read_u32(base + 8)? as u64;
+            (address_low | (address_high << 32), base + 12)
+        } else {
+            (address_low, base + 8)
+        };
+
+        // The mask and pending bits follow the data, which is padded to a
+        // double word.
+        let (mask, pending) = if per_vector_masking {
+            (
+                Some(self.read_u32(data_offset + 4)?),
+                Some(self.read_u32(data_offset + 8)?),
+            )
+        } else {
+            (None, None)
+        };
+
+        Some(PciMsiCapability {
+            enabled: control & 0x0001 != 0,
+            multiple_message_capable: 1 << ((control >> 1) & 0x7).min(5),
+            multiple_message_enabled: 1 << ((control >> 4) & 0x7).min(5),
+            is_64bit,
+            per_vector_masking,
+            address,
+            data: self.read_u16(data_offset)?,
+            mask,
+            pending,
+        })
+    }
+
+    /// Decodes the MSI-X capability, if the device has one.
+    pub fn msix(&self) -> Option<PciMsiXCapability> {
+        let base = self.capability(PCI_CAP_ID_MSIX)?.offset as usize;
+        let control = self.read_u16(base + 2)?;
+        let table = self.read_u32(base + 4)?;
+        let pba = self.read_u32(base + 8)?;
+
+        Some(PciMsiXCapability {
+            enabled: control & 0x8000 != 0,
+            function_mask: control & 0x4000 != 0,
+            table_size: (control & 0x07ff) + 1,
+            table_bir: (table & 0x7) as u8,
+            table_offset: table & !0x7,
+            pba_bir: (pba & 0x7) as u8,
+            pba_offset: pba & !0x7,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::tests::config_with_capabilities;
+
+    /// Test decoding a 64-bit MSI capability with per-vector masking.
+    #[test]
+    fn test_msi() {
+        let body = [
+            &[PCI_CAP_ID_MSI, 0][..],
+            // Enabled, 8 vectors capable and enabled, 64-bit, per-vector
+            // masking.
+            &0x01b7u16.to_le_bytes(),
+            &0xfee0_1000u32.to_le_bytes(),
+            &0x0000_0001u32.to_le_bytes(),
+            &0x0000_4023u32.to_le_bytes(),
+            &0x0000_000fu32.to_le_bytes(),
+            &0x0000_0002u32.to_le_bytes(),
+        ]
+        .concat();
+        let config = config_with_capabilities(&[(0x50, &body)]);
+
+        let msi = config.msi().unwrap();
+        assert!(msi.enabled);
+        assert!(msi.is_64bit);
+        assert!(msi.per_vector_masking);
+        assert_eq!(msi.multiple_message_capable, 8);
+        assert_eq!(msi.multiple_message_enabled, 8);
+        assert_eq!(msi.address, 0x1_fee0_1000);
+        assert_eq!(msi.data, 0x4023);
+        assert_eq!(msi.mask, Some(0xf));
+        assert_eq!(msi.pending, Some(0x2));
+        assert!(config.msix().is_none());
+    }
+
+    /// Test decoding an MSI-X capability and its table.
+    #[test]
+    fn test_msix() {
+        let body = [
+            &[PCI_CAP_ID_MSIX, 0][..],
+            // Enabled, function masked, 2 entries.
+            &0xc001u16.to_le_bytes(),
+            // Table in BAR 0 at 0x2000, PBA in BAR 2 at 0x3000.
+            &0x0000_2000u32.to_le_bytes(),
+            &0x0000_3002u32.to_le_bytes(),
+        ]
+        .concat();
+        let config = config_with_capabilities(&[(0x98, &body)]);
+
+        let msix = config.msix().unwrap();
+        assert!(msix.enabled);
+        assert!(msix.function_mask);
+        assert_eq!(msix.table_size, 2);
+        assert_eq!((msix.table_bir, msix.table_offset), (0, 0x2000));
+        assert_eq!((msix.pba_bir, msix.pba_offset), (2, 0x3000));
+
+        // The second entry is masked.
+        let table: Vec<u8> = [[0xfee0_1000u32, 0, 0x21, 0], [0xfee0_2000, 0, 0x22, 1]]
+            .iter()
+            .flatten()
+            .flat_map(|x| x.to_le_bytes())
+            .collect();
+        let entries = PciMsiXEntry::from_table(&table);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].address, 0xfee0_1000);
+        assert_eq!(entries[0].data, 0x21);
+        assert!(!entries[0].masked);
+        assert!(entries[1].masked);
+    }
+}
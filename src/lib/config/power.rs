@@ -0,0 +1,193 @@
+// Copyright (c) 2024 Gibson Pilconis, shibedrill, and contributors
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! # About this module
+//! The Power Management capability tells you which device power states
+//! (D-states) a device supports, which of them it can signal a wakeup (PME)
+//! from, and which state it is currently in:
+//! ```rust
+//! # use libpci_rs::pci::get_pci_list;
+//! for device in get_pci_list().unwrap() {
+//!     let Some(address) = device.address else { continue };
+//!     let Ok(config) = address.config_space() else { continue };
+//!     if let Some(pm) = config.power_management() {
+//!         println!("{}: {} (supports {:?})", address, pm.power_state, pm.supported_states);
+//!     }
+//! }
+//! ```
+
+use crate::config::{PciConfigSpace, PCI_CAP_ID_PM};
+use crate::pci::PciEnumerationError;
+
+use std::fmt::{Display, Formatter, Result};
+use std::str::FromStr;
+
+/// A device power state.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PciPowerState {
+    /// Fully on.
+    D0,
+    /// Light sleep, optional.
+    D1,
+    /// Deeper sleep, optional.
+    D2,
+    /// Off, but still powered and visible in configuration space.
+    D3Hot,
+    /// Off, with main power removed.
+    D3Cold,
+    /// The state could not be determined.
+    Unknown,
+}
+
+/// A decoded Power Management capability.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PciPmCapability {
+    /// The version of the Power Management specification the device
+    /// complies with.
+    pub version: u8,
+    /// The power states the device supports.
+    pub supported_states: Vec<PciPowerState>,
+    /// The power states the device can signal a wakeup (PME) from.
+    pub pme_support: Vec<PciPowerState>,
+    /// The power state the device is currently in.
+    pub power_state: PciPowerState,
+    /// Whether the device keeps its configuration when going from D3hot to
+    /// D0. If not, it goes through a reset and needs to be reconfigured.
+    pub no_soft_reset: bool,
+    /// Whether the device is allowed to signal a wakeup.
+    pub pme_enabled: bool,
+    /// Whether the device has signalled a wakeup.
+    pub pme_status: bool,
+}
+
+impl Display for PciPowerState {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::D0 => "D0",
+                Self::D1 => "D1",
+                Self::D2 => "D2",
+                Self::D3Hot => "D3hot",
+                Self::D3Cold => "D3cold",
+                Self::Unknown => "unknown",
+            }
+        )
+    }
+}
+
+// Parses the names the Linux kernel uses for power states.
+impl FromStr for PciPowerState {
+    type Err = PciEnumerationError;
+
+    fn from_str(input: &str) -> std::result::Result<Self, Self::Err> {
+        match input.trim() {
+            "D0" => Ok(Self::D0),
+            "D1" => Ok(Self::D1),
+            "D2" => Ok(Self::D2),
+            "D3hot" => Ok(Self::D3Hot),
+            "D3cold" => Ok(Self::D3Cold),
+            "unknown" | "error" => Ok(Self::Unknown),
+            other => Err(PciEnumerationError::InvalidAttribute(other.to_owned())),
+        }
+    }
+}
+
+impl PciConfigSpace {
+    /// Decodes the Power Management capability, if the device has one.
+    pub fn power_management(&self) -> Option<PciPmCapability> {
+        let base = self.capability(PCI_CAP_ID_PM)?.offset as usize;
+        let capabilities = self.read_u16(base + 2)?;
+        let control = self.read_u16(base + 4)?;
+
+        let mut supported_states = vec![PciPowerState::D0];
+        if capabilities & 0x0200 != 0 {
+            supported_states.push(PciPowerState::D1);
+        }
+        if capabilities & 0x0400 != 0 {
+            supported_states.push(PciPowerState::D2);
+        }
+        supported_states.push(PciPowerState::D3Hot);
+
+        let pme_support = [
+            PciPowerState::D0,
+            PciPowerState::D1,
+            PciPowerState::D2,
+            PciPowerState::D3Hot,
+            PciPowerState::D3Cold,
+        ]
+        .into_iter()
+        .enumerate()
+        .filter(|(bit, _)| capabilities & (0x0800 << bit) != 0)
+        .map(|(_, state)| state)
+        .collect();
+
+        Some(PciPmCapability {
+            version: (capabilities & 0x7) as u8,
+            supported_states,
+            pme_support,
+            power_state: match control & 0x3 {
+                0 => PciPowerState::D0,
+                1 => PciPowerState::D1,
+                2 => PciPowerState::D2,
+                _ => PciPowerState::D3Hot,
+            },
+            no_soft_reset: control & 0x0008 != 0,
+            pme_enabled: control & 0x0100 != 0,
+            pme_status: control & 0x8000 != 0,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::tests::config_with_capabilities;
+
+    /// Test decoding a Power Management capability.
+    #[test]
+    fn test_power_management() {
+        let body = [
+            &[PCI_CAP_ID_PM, 0][..],
+            // Version 3, D1 supported, PME from D0, D3hot and D3cold.
+            &0xca03u16.to_le_bytes(),
+            // D3hot, No_Soft_Reset, PME enabled.
+            &0x010bu16.to_le_bytes(),
+        ]
+        .concat();
+        let config = config_with_capabilities(&[(0x40, &body)]);
+
+        let pm = config.power_management().unwrap();
+        assert_eq!(pm.version, 3);
+        assert_eq!(
+            pm.supported_states,
+            vec![PciPowerState::D0, PciPowerState::D1, PciPowerState::D3Hot]
+        );
+        assert_eq!(
+            pm.pme_support,
+            vec![
+                PciPowerState::D0,
+                PciPowerState::D3Hot,
+                PciPowerState::D3Cold
+            ]
+        );
+        assert_eq!(pm.power_state, PciPowerState::D3Hot);
+        assert!(pm.no_soft_reset);
+        assert!(pm.pme_enabled);
+        assert!(!pm.pme_status);
+    }
+
+    /// Test converting power states to and from their kernel names.
+    #[test]
+    fn test_power_state_names() {
+        for state in [
+            PciPowerState::D0,
+            PciPowerState::D3Hot,
+            PciPowerState::D3Cold,
+        ] {
+            assert_eq!(state.to_string().parse::<PciPowerState>().unwrap(), state);
+        }
+        assert!("D4".parse::<PciPowerState>().is_err());
+    }
+}
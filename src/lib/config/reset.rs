@@ -0,0 +1,61 @@
+// Copyright (c) 2024 Gibson Pilconis, shibedrill, and contributors
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! # About this module
+//! A Function Level Reset resets a single function of a device without
+//! touching the rest of the bus, which makes it the preferred way of handing
+//! a device over to a new virtual machine. PCI Express devices advertise it
+//! in their Device Capabilities register, while conventional PCI devices can
+//! advertise it through the Advanced Features capability:
+//! ```rust
+//! # use libpci_rs::pci::get_pci_list;
+//! for device in get_pci_list().unwrap() {
+//!     let Some(address) = device.address else { continue };
+//!     let Ok(config) = address.config_space() else { continue };
+//!     println!("{}: FLR {:?}, AF FLR {:?}", address, config.flr_capable(), config.af_flr_capable());
+//! }
+//! ```
+
+use crate::config::{PciConfigSpace, PCI_CAP_ID_AF, PCI_CAP_ID_EXP};
+
+impl PciConfigSpace {
+    /// Gets whether the device advertises Function Level Reset in its PCI
+    /// Express Device Capabilities. Returns [`None`] if the device is not a
+    /// PCI Express device.
+    pub fn flr_capable(&self) -> Option<bool> {
+        let base = self.capability(PCI_CAP_ID_EXP)?.offset as usize;
+        Some(self.read_u32(base + 0x04)? & 0x1000_0000 != 0)
+    }
+
+    /// Gets whether the device advertises Function Level Reset in its
+    /// Advanced Features capability. Returns [`None`] if the device does not
+    /// have one.
+    pub fn af_flr_capable(&self) -> Option<bool> {
+        let base = self.capability(PCI_CAP_ID_AF)?.offset as usize;
+        // Both Transactions Pending and FLR have to be supported.
+        Some(self.read_u8(base + 3)? & 0x3 == 0x3)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::tests::config_with_capabilities;
+
+    /// Test decoding FLR support from the PCI Express and Advanced Features
+    /// capabilities.
+    #[test]
+    fn test_flr_capable() {
+        let mut express = vec![0u8; 0x08];
+        express[0] = PCI_CAP_ID_EXP;
+        express[0x04..0x08].copy_from_slice(&0x1000_8fc0u32.to_le_bytes());
+        let config =
+            config_with_capabilities(&[(0x70, &express), (0xa0, &[PCI_CAP_ID_AF, 0, 6, 0x1])]);
+        assert_eq!(config.flr_capable(), Some(true));
+        assert_eq!(config.af_flr_capable(), Some(false));
+
+        let config = config_with_capabilities(&[(0x40, &[PCI_CAP_ID_AF, 0, 6, 0x3])]);
+        assert_eq!(config.flr_capable(), None);
+        assert_eq!(config.af_flr_capable(), Some(true));
+    }
+}
@@ -8,10 +8,12 @@
 //! on a device you don't have installed, with VID `0x8086` and DID `0xA0F0`,
 //! you can chain these calls to get a device or vendor entry like so:
 //! ```rust
+//! # #[cfg(not(any(feature = "pciids-vendors-only", feature = "pciids-no-subsystems")))] {
 //! # use libpci_rs::ids::{PciDeviceEntry, PciVendorEntry, lookup_vendor};
 //! let ven_entry: PciVendorEntry = lookup_vendor(0x8086).unwrap();
-//! let dev_entry: &PciDeviceEntry = ven_entry.device(0xA0F0).unwrap();
+//! let dev_entry: PciDeviceEntry = ven_entry.device(0xA0F0).unwrap();
 //! assert_eq!("Wi-Fi 6 AX201", dev_entry.name());
+//! # }
 //! ```
 //! Similarly, you can get an ID entry regarding a vendor, and get its name:
 //! ```rust
@@ -29,11 +31,77 @@
 //! let pci_device: &PciDeviceHardware = pci_list.get(0).unwrap();
 //! println!("{}", pci_device.device_name().unwrap_or("Unknown device name".to_string()));
 //! ```
+//! The database compiled into the library goes out of date as new devices are
+//! released. If the system has a newer copy of pci.ids, you can load it at
+//! runtime and have it take precedence over the compiled-in database:
+//! ```rust
+//! # use libpci_rs::ids::{PciIdsDatabase, set_runtime_database};
+//! if let Ok(database) = PciIdsDatabase::load_default() {
+//!     set_runtime_database(Some(database));
+//! }
+//! ```
 
 #![allow(dead_code)]
 
+#[cfg(feature = "pciids-compact")]
+pub(crate) mod compact;
+pub mod export;
+pub mod parser;
+pub mod search;
+
+pub use crate::ids::parser::{ParseError, ParseErrorKind};
+
+use crate::class::{builtin_class, PciClassEntry};
+#[cfg(feature = "pciids-compact")]
+use crate::ids::compact::{builtin_vendor, builtin_vendor_ids};
+use crate::ids::parser::{ingest_pciids, ParsedIds, ParsedVendor};
+
+use std::collections::BTreeSet;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::{Once, RwLock, RwLockReadGuard};
+
 include!(concat!(env!("OUT_DIR"), "/pci_devices_phf.rs"));
 
+/// The locations [`PciIdsDatabase::load_default`] looks for pci.ids in, in
+/// order.
+pub const DEFAULT_PCI_IDS_PATHS: &[&str] = &[
+    "/usr/share/hwdata/pci.ids",
+    #[cfg(feature = "gzip")]
+    "/usr/share/hwdata/pci.ids.gz",
+    "/usr/share/misc/pci.ids",
+    #[cfg(feature = "gzip")]
+    "/usr/share/misc/pci.ids.gz",
+    "/usr/share/pci.ids",
+    "/usr/local/share/hwdata/pci.ids",
+    "/usr/local/share/pciids/pci.ids",
+];
+
+// The first bytes of every gzip file.
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// The environment variable holding the path of a local
+/// [ID overrides](set_id_overrides) file.
+pub const IDS_OVERRIDE_ENV: &str = "LIBPCI_RS_IDS_OVERRIDE";
+
+// The databases consulted before the compiled-in one.
+struct PciIdsLayers {
+    runtime: Option<PciIdsDatabase>,
+    overrides: Option<PciIdsDatabase>,
+    // The overrides applied on top of the entries they override.
+    merged: Option<PciIdsDatabase>,
+    // Every merge done so far, keyed by the runtime database and overrides
+    // it was made from, so that setting the same databases again doesn't
+    // merge and leak them again.
+    merges: Vec<(Option<PciIdsDatabase>, PciIdsDatabase, PciIdsDatabase)>,
+}
+
+static LAYERS: RwLock<PciIdsLayers> = RwLock::new(PciIdsLayers::new());
+static ENV_OVERRIDES: Once = Once::new();
+
 /// An ID entry representing a PCI device vendor.
 #[derive(Copy, Clone)]
 pub struct PciVendorEntry {
@@ -42,7 +110,7 @@ pub struct PciVendorEntry {
     /// The name of the vendor.
     name: &'static str,
     /// The list of devices manufactured by the vendor.
-    devices: &'static [PciDeviceEntry],
+    devices: Entries<PciDeviceEntry>,
 }
 
 /// An ID entry representing a PCI device.
@@ -53,7 +121,7 @@ pub struct PciDeviceEntry {
     /// The name of the device.
     name: &'static str,
     /// The list of possible subsystems for the device.
-    subsystems: &'static [PciSubsystemEntry],
+    subsystems: Entries<PciSubsystemEntry>,
 }
 
 /// An ID entry representing a PCI device subsystem.
@@ -67,13 +135,679 @@ pub struct PciSubsystemEntry {
     name: &'static str,
 }
 
-/// Gets a vendor with a given ID, if there is one.
+/// The name of a device subsystem, as shown by pciutils. If the subsystem
+/// isn't listed under its device, it can still be named generically after
+/// its vendor, like `Dell Device 0a2b`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SubsystemName {
+    /// The integer subsystem vendor ID.
+    pub subvendor_id: u16,
+    /// The integer subsystem device ID.
+    pub subdevice_id: u16,
+    /// The name of the subsystem vendor, from the vendor table.
+    pub vendor_name: Option<&'static str>,
+    /// The name of the subsystem, if it is listed under its device.
+    pub name: Option<&'static str>,
+}
+
+/// A copy of the PCI IDs database loaded at runtime.
+///
+/// Loaded databases are kept in memory for the rest of the program, so that
+/// their entries can hand out `&'static` names just like the compiled-in
+/// database. Load a database once and reuse it rather than loading it
+/// repeatedly.
+#[derive(Copy, Clone)]
+pub struct PciIdsDatabase {
+    /// The vendors in the database, sorted by ID.
+    vendors: &'static [PciVendorEntry],
+    /// The classes in the database, sorted by ID.
+    classes: &'static [PciClassEntry],
+    /// The version from the header of the database.
+    version: Option<&'static str>,
+    /// The date from the header of the database.
+    date: Option<&'static str>,
+}
+
+/// The number of entries of each kind in a PCI IDs database.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct PciIdsStats {
+    /// The number of vendors.
+    pub vendors: usize,
+    /// The number of devices, across all vendors.
+    pub devices: usize,
+    /// The number of subsystems, across all devices.
+    pub subsystems: usize,
+    /// The number of classes.
+    pub classes: usize,
+}
+
+/// A list of errors that can occur when loading a PCI IDs database.
+#[derive(Debug)]
+pub enum PciIdsError {
+    /// Unable to read the database.
+    Io(std::io::Error),
+    /// No database was found in any of the default locations.
+    NotFound,
+    /// The database is gzip-compressed, but the [gzip](crate#the-gzip-feature)
+    /// feature is disabled.
+    Compressed,
+    /// The database is malformed.
+    Parse(ParseError),
+}
+
+/// Gets a vendor with a given ID, if there is one. The
+/// [ID overrides](set_id_overrides) and the
+/// [runtime database](set_runtime_database) are consulted first, if set.
 pub fn lookup_vendor(vid: u16) -> Option<PciVendorEntry> {
+    lookup_layered(|x| x.vendor(vid)).or_else(|| builtin_vendor(vid))
+}
+
+/// Gets the name of a subsystem of a device. Unlike
+/// [`PciDeviceEntry::subsystem`], this still returns a name made from the
+/// subsystem vendor when the subsystem isn't listed under the device.
+/// Returns [`None`] if neither is known, or if the subsystem vendor ID is
+/// `0x0000` or `0xffff`, which mean the device has no subsystem.
+pub fn lookup_subsystem(
+    vid: u16,
+    did: u16,
+    subvendor: u16,
+    subdevice: u16,
+) -> Option<SubsystemName> {
+    if subvendor == 0x0000 || subvendor == 0xffff {
+        return None;
+    }
+
+    let name = lookup_vendor(vid)
+        .and_then(|x| x.device(did))
+        .and_then(|x| x.subsystem(subdevice, subvendor))
+        .map(|x| x.name);
+    let vendor_name = lookup_vendor(subvendor).map(|x| x.name);
+    if name.is_none() && vendor_name.is_none() {
+        return None;
+    }
+
+    Some(SubsystemName {
+        subvendor_id: subvendor,
+        subdevice_id: subdevice,
+        vendor_name,
+        name,
+    })
+}
+
+/// Sets the database consulted before the compiled-in one by
+/// [`lookup_vendor`], [`lookup_class`](crate::class::lookup_class) and the
+/// naming methods of [`PciDeviceHardware`](crate::pci::PciDeviceHardware).
+/// Entries in it take precedence over compiled-in ones. Pass [`None`] to go
+/// back to only using the compiled-in database.
+///
+/// If [ID overrides](set_id_overrides) are set, they are merged with the new
+/// database, and like loaded databases, the merged entries are kept in
+/// memory for the rest of the program. Merges are reused, so switching
+/// between the same databases doesn't use more memory, but every new
+/// combination of runtime database and overrides does.
+pub fn set_runtime_database(database: Option<PciIdsDatabase>) {
+    update_layers(|x| x.runtime = database);
+}
+
+/// Returns the database set with [`set_runtime_database`], if any.
+pub fn runtime_database() -> Option<PciIdsDatabase> {
+    read_layers().runtime
+}
+
+/// Sets local ID overrides, which take precedence over both the
+/// [runtime database](set_runtime_database) and the compiled-in one. Unlike
+/// the runtime database, overrides only replace the entries they contain:
+/// a device listed under a vendor is added to the vendor's devices, or
+/// replaces the device with the same ID, and the same goes for subsystems,
+/// subclasses and programming interfaces. Overrides use the pci.ids format,
+/// so they can be loaded with [`PciIdsDatabase::load`]:
+/// ```text
+/// 8086  Intel Corporation
+/// \t7f00  Pre-production widget
+/// ```
+/// If [`IDS_OVERRIDE_ENV`] holds the path of an overrides file, it is loaded
+/// on the first lookup, unless overrides were already set with this
+/// function. Pass [`None`] to remove the overrides.
+///
+/// Overrides are merged with the entries they override, and like loaded
+/// databases, the merged entries are kept in memory for the rest of the
+/// program. Merges are reused, so setting the same overrides again doesn't
+/// use more memory, but every new combination of overrides and
+/// [runtime database](set_runtime_database) does.
+pub fn set_id_overrides(overrides: Option<PciIdsDatabase>) {
+    // Overrides set explicitly win over the environment.
+    ENV_OVERRIDES.call_once(|| ());
+    update_layers(|x| x.overrides = overrides);
+}
+
+/// Returns the overrides set with [`set_id_overrides`] or loaded from
+/// [`IDS_OVERRIDE_ENV`], if any.
+pub fn id_overrides() -> Option<PciIdsDatabase> {
+    load_env_overrides();
+    read_layers().overrides
+}
+
+// Looks something up in the overrides, then in the runtime database.
+pub(crate) fn lookup_layered<T, F>(lookup: F) -> Option<T>
+where
+    F: Fn(&PciIdsDatabase) -> Option<T>,
+{
+    load_env_overrides();
+    read_layers().lookup(lookup)
+}
+
+/// Iterates over every vendor, sorted by ID. Vendors from the
+/// [ID overrides](set_id_overrides) and the
+/// [runtime database](set_runtime_database) are included, and take
+/// precedence over compiled-in ones just like with [`lookup_vendor`].
+pub fn all_vendors() -> impl Iterator<Item = PciVendorEntry> {
+    let mut ids: BTreeSet<u16> = builtin_vendor_ids().collect();
+    for database in layer_databases() {
+        ids.extend(database.vendors().map(|x| x.id));
+    }
+    ids.into_iter().filter_map(lookup_vendor)
+}
+
+/// Returns the number of vendors [`all_vendors`] iterates over.
+pub fn vendor_count() -> usize {
+    all_vendors().count()
+}
+
+/// Returns the version of the database in use, as found in its header: the
+/// [runtime database](set_runtime_database) if one is set, otherwise the
+/// compiled-in one.
+pub fn database_version() -> Option<&'static str> {
+    match runtime_database() {
+        Some(database) => database.version,
+        None => BUILTIN_VERSION,
+    }
+}
+
+/// Returns the date of the database in use, as found in its header: the
+/// [runtime database](set_runtime_database) if one is set, otherwise the
+/// compiled-in one.
+pub fn database_date() -> Option<&'static str> {
+    match runtime_database() {
+        Some(database) => database.date,
+        None => BUILTIN_DATE,
+    }
+}
+
+/// Counts the entries [`all_vendors`] and
+/// [`all_classes`](crate::class::all_classes) iterate over.
+pub fn database_stats() -> PciIdsStats {
+    if layer_databases().is_empty() {
+        // Counted by the build script.
+        return BUILTIN_STATS;
+    }
+    PciIdsStats::count(all_vendors(), crate::class::all_classes())
+}
+
+// The databases consulted before the compiled-in one.
+pub(crate) fn layer_databases() -> Vec<PciIdsDatabase> {
+    load_env_overrides();
+    let layers = read_layers();
+    [layers.runtime, layers.merged]
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+#[cfg(not(feature = "pciids-compact"))]
+fn builtin_vendor(vid: u16) -> Option<PciVendorEntry> {
     let result = VENDORS.get(&vid);
     Some(*result?)
 }
 
+#[cfg(not(feature = "pciids-compact"))]
+fn builtin_vendor_ids() -> impl Iterator<Item = u16> {
+    VENDORS.keys().copied()
+}
+
+fn load_env_overrides() {
+    ENV_OVERRIDES.call_once(|| {
+        // Lookups can't report errors, so an unreadable or malformed file is
+        // ignored.
+        if let Some(path) = std::env::var_os(IDS_OVERRIDE_ENV) {
+            if let Ok(overrides) = PciIdsDatabase::load(path) {
+                update_layers(|x| x.overrides = Some(overrides));
+            }
+        }
+    });
+}
+
+fn read_layers() -> RwLockReadGuard<'static, PciIdsLayers> {
+    LAYERS.read().unwrap_or_else(|poison| poison.into_inner())
+}
+
+fn update_layers<F: FnOnce(&mut PciIdsLayers)>(update: F) {
+    let mut layers = LAYERS.write().unwrap_or_else(|poison| poison.into_inner());
+    update(&mut layers);
+    layers.merge();
+}
+
+impl PciIdsLayers {
+    const fn new() -> Self {
+        PciIdsLayers {
+            runtime: None,
+            overrides: None,
+            merged: None,
+            merges: Vec::new(),
+        }
+    }
+
+    // Looks something up in the overrides, then in the runtime database.
+    fn lookup<T, F>(&self, lookup: F) -> Option<T>
+    where
+        F: Fn(&PciIdsDatabase) -> Option<T>,
+    {
+        self.merged
+            .as_ref()
+            .and_then(&lookup)
+            .or_else(|| self.runtime.as_ref().and_then(&lookup))
+    }
+
+    // Applies the overrides on top of the runtime database, reusing an
+    // earlier merge of the same databases if there is one.
+    fn merge(&mut self) {
+        let runtime = self.runtime;
+        self.merged = self.overrides.map(|overrides| {
+            let cached = self.merges.iter().find(|(x, y, _)| {
+                same_database(*x, runtime) && same_database(Some(*y), Some(overrides))
+            });
+            match cached {
+                Some((_, _, merged)) => *merged,
+                None => {
+                    let merged = overrides.merged_onto(runtime);
+                    self.merges.push((runtime, overrides, merged));
+                    merged
+                }
+            }
+        });
+    }
+}
+
+// Whether two databases are the same loaded database, rather than just ones
+// with the same entries.
+fn same_database(a: Option<PciIdsDatabase>, b: Option<PciIdsDatabase>) -> bool {
+    match (a, b) {
+        (None, None) => true,
+        (Some(a), Some(b)) => {
+            std::ptr::eq(a.vendors, b.vendors)
+                && std::ptr::eq(a.classes, b.classes)
+                && a.version == b.version
+                && a.date == b.date
+        }
+        _ => false,
+    }
+}
+
+// An entry listed under a vendor, device, class or subclass.
+pub(crate) trait ChildEntry: Copy + 'static {
+    type Key: Ord;
+
+    // Returns the key the entries of a list are sorted by.
+    fn key(&self) -> Self::Key;
+
+    // Reads the entry from a record of the compact blob.
+    #[cfg(feature = "pciids-compact")]
+    fn decode(index: usize) -> Self;
+}
+
+// The entries listed under a vendor, device, class or subclass, sorted by
+// key. With the pciids-compact feature, the compiled-in entries stay in the
+// blob, and are read from their records as they are looked up.
+#[derive(Copy, Clone)]
+pub(crate) enum Entries<T: 'static> {
+    Slice(&'static [T]),
+    #[cfg(feature = "pciids-compact")]
+    Compact {
+        first: usize,
+        count: usize,
+    },
+}
+
+impl<T: ChildEntry> Entries<T> {
+    pub(crate) fn len(&self) -> usize {
+        match self {
+            Entries::Slice(entries) => entries.len(),
+            #[cfg(feature = "pciids-compact")]
+            Entries::Compact { count, .. } => *count,
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Returns the entry at `index`, which must be less than the length.
+    fn at(&self, index: usize) -> T {
+        match self {
+            Entries::Slice(entries) => entries[index],
+            #[cfg(feature = "pciids-compact")]
+            Entries::Compact { first, .. } => T::decode(first + index),
+        }
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = T> {
+        let entries = *self;
+        (0..self.len()).map(move |x| entries.at(x))
+    }
+
+    // Finds an entry by key.
+    pub(crate) fn find(&self, wanted: T::Key) -> Option<T> {
+        let (mut low, mut high) = (0, self.len());
+        while low < high {
+            let middle = low + (high - low) / 2;
+            let entry = self.at(middle);
+            match entry.key().cmp(&wanted) {
+                std::cmp::Ordering::Less => low = middle + 1,
+                std::cmp::Ordering::Greater => high = middle,
+                std::cmp::Ordering::Equal => return Some(entry),
+            }
+        }
+        None
+    }
+
+    // Returns the entries as a list, or None if there are none.
+    pub(crate) fn to_vec(self) -> Option<Vec<T>> {
+        match self.is_empty() {
+            true => None,
+            false => Some(self.iter().collect()),
+        }
+    }
+}
+
+// Replaces the entries of `base` with the entries of `overlay` that have the
+// same key, merging them with `merge`, and adds the rest. The result is sorted
+// by key like both lists.
+pub(crate) fn merge_entries<T, M>(base: Entries<T>, overlay: Entries<T>, merge: M) -> Entries<T>
+where
+    T: ChildEntry,
+    M: Fn(T, T) -> T,
+{
+    let mut result: Vec<T> = base.iter().collect();
+    for entry in overlay.iter() {
+        match result.binary_search_by_key(&entry.key(), |x| x.key()) {
+            Ok(index) => result[index] = merge(result[index], entry),
+            Err(index) => result.insert(index, entry),
+        }
+    }
+    Entries::Slice(result.leak())
+}
+
+// Finds an entry in a list sorted by key.
+pub(crate) fn find_sorted<T, K, F>(entries: &[T], wanted: K, key: F) -> Option<&T>
+where
+    K: Ord,
+    F: Fn(&T) -> K,
+{
+    let index = entries.binary_search_by_key(&wanted, key).ok()?;
+    entries.get(index)
+}
+
+impl PciIdsDatabase {
+    /// Loads a database from a pci.ids file. Gzip-compressed files are
+    /// decompressed if the [gzip](crate#the-gzip-feature) feature is enabled.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, PciIdsError> {
+        let raw = fs::read(path)?;
+        if raw.starts_with(&GZIP_MAGIC) {
+            return Self::from_gzip(&raw);
+        }
+        String::from_utf8(raw)
+            .map_err(|err| std::io::Error::new(ErrorKind::InvalidData, err))?
+            .parse()
+    }
+
+    #[cfg(feature = "gzip")]
+    fn from_gzip(raw: &[u8]) -> Result<Self, PciIdsError> {
+        use std::io::Read;
+
+        let mut decompressed = String::new();
+        flate2::read::GzDecoder::new(raw).read_to_string(&mut decompressed)?;
+        decompressed.parse()
+    }
+
+    #[cfg(not(feature = "gzip"))]
+    fn from_gzip(_raw: &[u8]) -> Result<Self, PciIdsError> {
+        Err(PciIdsError::Compressed)
+    }
+
+    /// Loads the database from the first of the
+    /// [default locations](DEFAULT_PCI_IDS_PATHS) that has one.
+    pub fn load_default() -> Result<Self, PciIdsError> {
+        for path in DEFAULT_PCI_IDS_PATHS {
+            match Self::load(path) {
+                Err(PciIdsError::Io(err)) if err.kind() == ErrorKind::NotFound => continue,
+                result => return result,
+            }
+        }
+        Err(PciIdsError::NotFound)
+    }
+
+    /// Returns the version from the header of the database, if it has one.
+    pub fn version(&self) -> Option<&'static str> {
+        self.version
+    }
+
+    /// Returns the date from the header of the database, if it has one.
+    pub fn date(&self) -> Option<&'static str> {
+        self.date
+    }
+
+    /// Counts the entries in the database.
+    pub fn stats(&self) -> PciIdsStats {
+        PciIdsStats::count(self.vendors(), self.classes())
+    }
+
+    /// Iterates over the vendors in the database, sorted by ID.
+    pub fn vendors(&self) -> impl Iterator<Item = PciVendorEntry> {
+        self.vendors.iter().copied()
+    }
+
+    /// Iterates over the classes in the database, sorted by ID.
+    pub fn classes(&self) -> impl Iterator<Item = PciClassEntry> {
+        self.classes.iter().copied()
+    }
+
+    /// Gets a vendor with a given ID, if there is one.
+    pub fn vendor(&self, vid: u16) -> Option<PciVendorEntry> {
+        find_sorted(self.vendors, vid, |x| x.id).copied()
+    }
+
+    /// Gets a class with a given ID, if there is one.
+    pub fn class(&self, id: u8) -> Option<PciClassEntry> {
+        find_sorted(self.classes, id, |x| x.id()).copied()
+    }
+
+    // Applies the entries of this database on top of the entries of `base`,
+    // or of the compiled-in database.
+    fn merged_onto(&self, base: Option<PciIdsDatabase>) -> Self {
+        let vendors: Vec<PciVendorEntry> = self
+            .vendors
+            .iter()
+            .map(|x| {
+                match base
+                    .and_then(|y| y.vendor(x.id))
+                    .or_else(|| builtin_vendor(x.id))
+                {
+                    Some(existing) => PciVendorEntry::merge(existing, *x),
+                    None => *x,
+                }
+            })
+            .collect();
+        let classes: Vec<PciClassEntry> = self
+            .classes
+            .iter()
+            .map(|x| {
+                match base
+                    .and_then(|y| y.class(x.id()))
+                    .or_else(|| builtin_class(x.id()))
+                {
+                    Some(existing) => PciClassEntry::merge(existing, *x),
+                    None => *x,
+                }
+            })
+            .collect();
+
+        PciIdsDatabase {
+            vendors: vendors.leak(),
+            classes: classes.leak(),
+            version: self.version,
+            date: self.date,
+        }
+    }
+
+    fn from_parsed(mut parsed: ParsedIds) -> Self {
+        parsed.sort();
+        let vendors: Vec<PciVendorEntry> = parsed
+            .vendors
+            .into_iter()
+            .map(PciVendorEntry::leak)
+            .collect();
+        let classes: Vec<PciClassEntry> = parsed
+            .classes
+            .into_iter()
+            .map(PciClassEntry::leak)
+            .collect();
+
+        PciIdsDatabase {
+            vendors: vendors.leak(),
+            classes: classes.leak(),
+            version: parsed.version.map(leak_str),
+            date: parsed.date.map(leak_str),
+        }
+    }
+}
+
+impl PciIdsStats {
+    fn count<V, C>(vendors: V, classes: C) -> Self
+    where
+        V: Iterator<Item = PciVendorEntry>,
+        C: Iterator<Item = PciClassEntry>,
+    {
+        let mut stats = PciIdsStats {
+            classes: classes.count(),
+            ..Default::default()
+        };
+        for vendor in vendors {
+            stats.vendors += 1;
+            stats.devices += vendor.devices.len();
+            stats.subsystems += vendor
+                .devices
+                .iter()
+                .map(|x| x.subsystems.len())
+                .sum::<usize>();
+        }
+        stats
+    }
+}
+
+impl FromStr for PciIdsDatabase {
+    type Err = PciIdsError;
+
+    /// Parses the contents of a pci.ids file.
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        Ok(Self::from_parsed(ingest_pciids(input)?))
+    }
+}
+
+impl From<ParsedIds> for PciIdsDatabase {
+    /// Builds a database from the output of the [`parser`].
+    fn from(parsed: ParsedIds) -> Self {
+        Self::from_parsed(parsed)
+    }
+}
+
+// Prints the subsystem name if it is known, or a generic name made from the
+// subsystem vendor otherwise.
+impl Display for SubsystemName {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match (self.name, self.vendor_name) {
+            (Some(name), _) => write!(f, "{}", name),
+            (None, Some(vendor)) => write!(f, "{} Device {:04x}", vendor, self.subdevice_id),
+            (None, None) => write!(
+                f,
+                "Device {:04x}:{:04x}",
+                self.subvendor_id, self.subdevice_id
+            ),
+        }
+    }
+}
+
+impl Display for PciIdsError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "{}", err),
+            Self::NotFound => write!(f, "no PCI IDs database found"),
+            Self::Compressed => write!(
+                f,
+                "the PCI IDs database is compressed and the gzip feature is disabled"
+            ),
+            Self::Parse(err) => write!(f, "malformed PCI IDs database: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for PciIdsError {}
+
+// Convert IO errors to PCI IDs errors.
+impl From<std::io::Error> for PciIdsError {
+    fn from(err: std::io::Error) -> Self {
+        PciIdsError::Io(err)
+    }
+}
+
+// Convert parse errors to PCI IDs errors.
+impl From<ParseError> for PciIdsError {
+    fn from(err: ParseError) -> Self {
+        PciIdsError::Parse(err)
+    }
+}
+
+// Leaks a string so that it lives as long as the compiled-in names.
+pub(crate) fn leak_str(input: String) -> &'static str {
+    Box::leak(input.into_boxed_str())
+}
+
 impl PciVendorEntry {
+    fn leak(parsed: ParsedVendor) -> Self {
+        let devices: Vec<PciDeviceEntry> = parsed
+            .devices
+            .into_iter()
+            .map(|device| {
+                let subsystems: Vec<PciSubsystemEntry> = device
+                    .subsystems
+                    .into_iter()
+                    .map(|subsystem| PciSubsystemEntry {
+                        subvendor: subsystem.subvendor,
+                        subdevice: subsystem.subdevice,
+                        name: leak_str(subsystem.name),
+                    })
+                    .collect();
+                PciDeviceEntry {
+                    id: device.id,
+                    name: leak_str(device.name),
+                    subsystems: Entries::Slice(subsystems.leak()),
+                }
+            })
+            .collect();
+
+        PciVendorEntry {
+            id: parsed.id,
+            name: leak_str(parsed.name),
+            devices: Entries::Slice(devices.leak()),
+        }
+    }
+
+    fn merge(base: Self, overlay: Self) -> Self {
+        PciVendorEntry {
+            id: overlay.id,
+            name: overlay.name,
+            devices: merge_entries(base.devices, overlay.devices, PciDeviceEntry::merge),
+        }
+    }
+
     /// Returns the vendor ID.
     pub fn id(&self) -> u16 {
         self.id
@@ -85,21 +819,25 @@ impl PciVendorEntry {
     }
 
     /// Gets a specific device by ID.
-    pub fn device(&self, did: u16) -> Option<&PciDeviceEntry> {
-        self.devices.iter().find(|x| x.id == did)
+    pub fn device(&self, did: u16) -> Option<PciDeviceEntry> {
+        self.devices.find(did)
     }
 
-    /// Gets all devices associated with a vendor.
-    pub fn devices(&self) -> Option<Vec<&PciDeviceEntry>> {
-        let ret: Vec<&PciDeviceEntry> = self.devices.iter().collect();
-        match ret.is_empty() {
-            true => None,
-            false => Some(ret),
-        }
+    /// Gets all devices associated with a vendor, sorted by ID.
+    pub fn devices(&self) -> Option<Vec<PciDeviceEntry>> {
+        self.devices.to_vec()
     }
 }
 
 impl PciDeviceEntry {
+    fn merge(base: Self, overlay: Self) -> Self {
+        PciDeviceEntry {
+            id: overlay.id,
+            name: overlay.name,
+            subsystems: merge_entries(base.subsystems, overlay.subsystems, |_, x| x),
+        }
+    }
+
     /// Returns the device ID.
     pub fn id(&self) -> u16 {
         self.id
@@ -110,22 +848,17 @@ impl PciDeviceEntry {
         self.name
     }
 
-    /// Gets all the subsystems associated with a device. Many devices do not
+    /// Gets all the subsystems associated with a device, sorted by subvendor
+    /// and subdevice ID. Many devices do not
     /// have subsystems, so it is common for this function to return None.
-    pub fn subsystems(&self) -> Option<Vec<&PciSubsystemEntry>> {
-        let ret: Vec<&PciSubsystemEntry> = self.subsystems.iter().collect();
-        match ret.is_empty() {
-            true => None,
-            false => Some(ret),
-        }
+    pub fn subsystems(&self) -> Option<Vec<PciSubsystemEntry>> {
+        self.subsystems.to_vec()
     }
 
     /// Gets a specific subsystem by ID. Many devices do not have subsystems,
     /// so it is common for this function to return None.
-    pub fn subsystem(&self, did: u16, vid: u16) -> Option<&PciSubsystemEntry> {
-        self.subsystems
-            .iter()
-            .find(|x| x.subdevice == did && x.subvendor == vid)
+    pub fn subsystem(&self, did: u16, vid: u16) -> Option<PciSubsystemEntry> {
+        self.subsystems.find((vid, did))
     }
 }
 
@@ -146,9 +879,214 @@ impl PciSubsystemEntry {
     }
 }
 
+impl ChildEntry for PciDeviceEntry {
+    type Key = u16;
+
+    fn key(&self) -> u16 {
+        self.id
+    }
+
+    #[cfg(feature = "pciids-compact")]
+    fn decode(index: usize) -> Self {
+        compact::decode_device(index)
+    }
+}
+
+impl ChildEntry for PciSubsystemEntry {
+    type Key = (u16, u16);
+
+    fn key(&self) -> (u16, u16) {
+        (self.subvendor, self.subdevice)
+    }
+
+    #[cfg(feature = "pciids-compact")]
+    fn decode(index: usize) -> Self {
+        compact::decode_subsystem(index)
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::ids::lookup_vendor;
+    use crate::ids::{
+        all_vendors, builtin_vendor, database_stats, database_version, lookup_vendor, vendor_count,
+        ParseErrorKind, PciIdsDatabase, PciIdsError, PciIdsLayers, PciIdsStats,
+    };
+
+    const PCI_IDS: &str = "\
+#
+#\tVersion: 2099.01.01
+#\tDate:    2099-01-01 00:00:00
+#
+fffe  Runtime Vendor
+\t0001  Runtime Device
+\t\tfffe 0002  Runtime Subsystem
+0014  Loongson Technology LLC
+C 09  Input device controller
+\t00  Keyboard controller
+\t03  USB controller
+\t\t00  Unspecified
+";
+
+    /// Test loading a database at runtime.
+    #[test]
+    fn test_load_database() {
+        let database: PciIdsDatabase = PCI_IDS.parse().unwrap();
+        let vendor = database.vendor(0xfffe).unwrap();
+        assert_eq!(vendor.name(), "Runtime Vendor");
+        let device = vendor.device(0x0001).unwrap();
+        assert_eq!(device.name(), "Runtime Device");
+        assert_eq!(
+            device.subsystem(0x0002, 0xfffe).unwrap().name(),
+            "Runtime Subsystem"
+        );
+        assert!(database.vendor(0x8086).is_none());
+
+        let class = database.class(0x09).unwrap();
+        let subclass = class.subclass(0x03).unwrap();
+        assert_eq!(subclass.name(), "USB controller");
+        assert_eq!(subclass.prog(0x00).unwrap().name(), "Unspecified");
+    }
+
+    /// Test that a malformed database is reported instead of loaded.
+    #[test]
+    fn test_load_malformed_database() {
+        let result = "fffe  Vendor\n\t0001\n".parse::<PciIdsDatabase>();
+        match result {
+            Err(PciIdsError::Parse(err)) => {
+                assert_eq!(err.line, 2);
+                assert_eq!(err.kind, ParseErrorKind::MissingName);
+            }
+            _ => panic!("expected a parse error"),
+        }
+    }
+
+    /// Test reading the metadata of a database.
+    #[test]
+    fn test_database_metadata() {
+        let database: PciIdsDatabase = PCI_IDS.parse().unwrap();
+        assert_eq!(database.version(), Some("2099.01.01"));
+        assert_eq!(database.date(), Some("2099-01-01 00:00:00"));
+        assert_eq!(
+            database.stats(),
+            PciIdsStats {
+                vendors: 2,
+                devices: 1,
+                subsystems: 1,
+                classes: 1,
+            }
+        );
+
+        // The fixture the library is built from has a header too.
+        assert!(database_version().is_some());
+        assert!(database_stats().vendors > 0);
+    }
+
+    /// Test loading a gzip-compressed database.
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn test_load_gzip_database() {
+        use flate2::write::GzEncoder;
+        use std::io::Write;
+
+        let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(PCI_IDS.as_bytes()).unwrap();
+        let path = std::env::temp_dir().join("libpci-rs-test-pci.ids.gz");
+        std::fs::write(&path, encoder.finish().unwrap()).unwrap();
+
+        let database = PciIdsDatabase::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(database.vendor(0xfffe).unwrap().name(), "Runtime Vendor");
+    }
+
+    /// Test runtime database entries taking precedence over compiled-in ones.
+    /// The layers are built locally rather than set globally, so that the
+    /// other lookup tests are not affected.
+    #[test]
+    fn test_runtime_database() {
+        let mut layers = PciIdsLayers::new();
+        layers.runtime = Some(PCI_IDS.parse().unwrap());
+        layers.merge();
+        let vendor = |vid| {
+            layers
+                .lookup(|x| x.vendor(vid))
+                .or_else(|| builtin_vendor(vid))
+        };
+
+        assert!(builtin_vendor(0xfffe).is_none());
+        assert_eq!(vendor(0xfffe).unwrap().name(), "Runtime Vendor");
+        // Entries missing from the runtime database still come from the
+        // compiled-in one.
+        assert!(vendor(0x10de).is_some());
+    }
+
+    /// Test local overrides adding to and replacing compiled-in entries.
+    #[cfg(not(any(feature = "pciids-vendors-only", feature = "pciids-no-subsystems")))]
+    #[test]
+    fn test_id_overrides() {
+        use crate::class::builtin_class;
+        use crate::ids::same_database;
+
+        let overrides = "\
+10de  NVIDIA Corporation
+\tfff0  Lab FPGA
+\t\t10de fff1  Lab FPGA Board
+C 09  Input device controller
+\t7f  Lab input controller
+";
+        let mut layers = PciIdsLayers::new();
+        layers.overrides = Some(overrides.parse().unwrap());
+        layers.merge();
+
+        let vendor = layers.lookup(|x| x.vendor(0x10de)).unwrap();
+        let device = vendor.device(0xfff0).unwrap();
+        assert_eq!(device.name(), "Lab FPGA");
+        assert_eq!(
+            device.subsystem(0xfff1, 0x10de).unwrap().name(),
+            "Lab FPGA Board"
+        );
+        // Devices that aren't overridden are kept.
+        assert_eq!(vendor.device(0x1056).unwrap().name(), "GF119M [NVS 4200M]");
+
+        let class = layers.lookup(|x| x.class(0x09)).unwrap();
+        assert_eq!(class.subclass(0x7f).unwrap().name(), "Lab input controller");
+        assert!(class.subclass(0x00).is_some());
+
+        // Setting the same overrides again reuses the earlier merge.
+        let merged = layers.merged;
+        layers.merge();
+        assert!(same_database(layers.merged, merged));
+        assert_eq!(layers.merges.len(), 1);
+
+        assert!(builtin_vendor(0x10de).unwrap().device(0xfff0).is_none());
+        assert!(builtin_class(0x09).unwrap().subclass(0x7f).is_none());
+    }
+
+    /// Test naming subsystems, with and without an entry under their device.
+    #[cfg(not(any(feature = "pciids-vendors-only", feature = "pciids-no-subsystems")))]
+    #[test]
+    fn test_lookup_subsystem() {
+        use crate::ids::lookup_subsystem;
+
+        let listed = lookup_subsystem(0x1af4, 0x1000, 0x01de, 0x0001).unwrap();
+        assert_eq!(listed.name, Some("Virtio network device"));
+        assert_eq!(listed.to_string(), "Virtio network device");
+
+        let generic = lookup_subsystem(0x1af4, 0x1000, 0x10de, 0x0a2b).unwrap();
+        assert_eq!(generic.name, None);
+        assert_eq!(generic.vendor_name, Some("NVIDIA Corporation"));
+        assert_eq!(generic.to_string(), "NVIDIA Corporation Device 0a2b");
+
+        assert!(lookup_subsystem(0x1af4, 0x1000, 0x0000, 0x0000).is_none());
+    }
+
+    /// Test iterating over every vendor.
+    #[test]
+    fn test_all_vendors() {
+        let ids: Vec<u16> = all_vendors().map(|x| x.id()).collect();
+        assert!(ids.windows(2).all(|x| x[0] < x[1]));
+        assert!(ids.contains(&0x10de));
+        assert!(vendor_count() > 0);
+    }
 
     /// Test looking up a specific vendor.
     #[test]
@@ -158,6 +1096,7 @@ mod tests {
     }
 
     /// Test looking up a specific device.
+    #[cfg(not(any(feature = "pciids-vendors-only", feature = "pciids-no-subsystems")))]
     #[test]
     fn test_get_device() {
         let vendor = lookup_vendor(0x10de).unwrap();
@@ -0,0 +1,212 @@
+// Copyright (c) 2024 Gibson Pilconis, shibedrill, and contributors
+// SPDX-License-Identifier: BSD-3-Clause
+
+// Reads the compact blob the build script generates with the pciids-compact
+// feature, see build/compact.rs for its layout. Nothing is decoded ahead of
+// time or kept around: each lookup reads the records it needs, with names
+// borrowed straight from the string table, and the entries under a vendor or
+// class refer to the range of records they occupy.
+
+use crate::class::{PciClassEntry, PciProgEntry, PciSubclassEntry};
+use crate::ids::{Entries, PciDeviceEntry, PciSubsystemEntry, PciVendorEntry};
+
+use std::sync::OnceLock;
+
+static BLOB: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/pci_ids.bin"));
+
+// The size of a name reference: offset u32, length u16.
+const NAME_SIZE: usize = 6;
+// The size of a reference to children: first index u32, count u32.
+const CHILDREN_SIZE: usize = 8;
+// The sizes of the records in each table.
+const RECORD_SIZES: [usize; 6] = [
+    2 + NAME_SIZE + CHILDREN_SIZE, // vendor
+    2 + NAME_SIZE + CHILDREN_SIZE, // device
+    4 + NAME_SIZE,                 // subsystem
+    1 + NAME_SIZE + CHILDREN_SIZE, // class
+    1 + NAME_SIZE + CHILDREN_SIZE, // subclass
+    1 + NAME_SIZE,                 // prog
+];
+const VENDORS: usize = 0;
+const DEVICES: usize = 1;
+const SUBSYSTEMS: usize = 2;
+const CLASSES: usize = 3;
+const SUBCLASSES: usize = 4;
+const PROGS: usize = 5;
+
+// Where each table, and the string table, start in the blob.
+struct Layout {
+    counts: [usize; 6],
+    starts: [usize; 6],
+    strings: usize,
+}
+
+static LAYOUT: OnceLock<Layout> = OnceLock::new();
+
+fn u16_at(offset: usize) -> u16 {
+    u16::from_le_bytes([BLOB[offset], BLOB[offset + 1]])
+}
+
+fn u32_at(offset: usize) -> u32 {
+    u32::from_le_bytes([
+        BLOB[offset],
+        BLOB[offset + 1],
+        BLOB[offset + 2],
+        BLOB[offset + 3],
+    ])
+}
+
+fn layout() -> &'static Layout {
+    LAYOUT.get_or_init(|| {
+        let mut counts = [0; 6];
+        let mut starts = [0; 6];
+        let mut offset = counts.len() * 4;
+        for table in 0..counts.len() {
+            counts[table] = u32_at(table * 4) as usize;
+            starts[table] = offset;
+            offset += counts[table] * RECORD_SIZES[table];
+        }
+        Layout {
+            counts,
+            starts,
+            strings: offset,
+        }
+    })
+}
+
+// Returns the offset of a record in the blob.
+fn record(table: usize, index: usize) -> usize {
+    layout().starts[table] + index * RECORD_SIZES[table]
+}
+
+fn name_at(offset: usize) -> &'static str {
+    let start = layout().strings + u32_at(offset) as usize;
+    let length = u16_at(offset + 4) as usize;
+    // The build script only writes valid UTF-8.
+    std::str::from_utf8(&BLOB[start..start + length]).unwrap_or_default()
+}
+
+// Returns the records a record's children occupy.
+fn children_at<T>(offset: usize) -> Entries<T> {
+    Entries::Compact {
+        first: u32_at(offset) as usize,
+        count: u32_at(offset + 4) as usize,
+    }
+}
+
+// Binary searches a sorted table for an ID.
+fn search<K: Ord>(table: usize, wanted: K, key: impl Fn(usize) -> K) -> Option<usize> {
+    let (mut low, mut high) = (0, layout().counts[table]);
+    while low < high {
+        let middle = low + (high - low) / 2;
+        match key(record(table, middle)).cmp(&wanted) {
+            std::cmp::Ordering::Less => low = middle + 1,
+            std::cmp::Ordering::Greater => high = middle,
+            std::cmp::Ordering::Equal => return Some(middle),
+        }
+    }
+    None
+}
+
+fn decode_vendor(index: usize) -> PciVendorEntry {
+    let offset = record(VENDORS, index);
+    PciVendorEntry {
+        id: u16_at(offset),
+        name: name_at(offset + 2),
+        devices: children_at(offset + 2 + NAME_SIZE),
+    }
+}
+
+pub(crate) fn decode_device(index: usize) -> PciDeviceEntry {
+    let offset = record(DEVICES, index);
+    PciDeviceEntry {
+        id: u16_at(offset),
+        name: name_at(offset + 2),
+        subsystems: children_at(offset + 2 + NAME_SIZE),
+    }
+}
+
+pub(crate) fn decode_subsystem(index: usize) -> PciSubsystemEntry {
+    let offset = record(SUBSYSTEMS, index);
+    PciSubsystemEntry {
+        subvendor: u16_at(offset),
+        subdevice: u16_at(offset + 2),
+        name: name_at(offset + 4),
+    }
+}
+
+fn decode_class(index: usize) -> PciClassEntry {
+    let offset = record(CLASSES, index);
+    PciClassEntry {
+        id: BLOB[offset],
+        name: name_at(offset + 1),
+        subclasses: children_at(offset + 1 + NAME_SIZE),
+    }
+}
+
+pub(crate) fn decode_subclass(index: usize) -> PciSubclassEntry {
+    let offset = record(SUBCLASSES, index);
+    PciSubclassEntry {
+        id: BLOB[offset],
+        name: name_at(offset + 1),
+        progs: children_at(offset + 1 + NAME_SIZE),
+    }
+}
+
+pub(crate) fn decode_prog(index: usize) -> PciProgEntry {
+    let offset = record(PROGS, index);
+    PciProgEntry {
+        id: BLOB[offset],
+        name: name_at(offset + 1),
+    }
+}
+
+pub(crate) fn builtin_vendor(vid: u16) -> Option<PciVendorEntry> {
+    search(VENDORS, vid, u16_at).map(decode_vendor)
+}
+
+pub(crate) fn builtin_vendor_ids() -> impl Iterator<Item = u16> {
+    (0..layout().counts[VENDORS]).map(|x| u16_at(record(VENDORS, x)))
+}
+
+pub(crate) fn builtin_class(id: u8) -> Option<PciClassEntry> {
+    search(CLASSES, id, |x| BLOB[x]).map(decode_class)
+}
+
+pub(crate) fn builtin_class_ids() -> impl Iterator<Item = u8> {
+    (0..layout().counts[CLASSES]).map(|x| BLOB[record(CLASSES, x)])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test decoding every entry of the blob.
+    #[test]
+    fn test_decode_blob() {
+        let ids: Vec<u16> = builtin_vendor_ids().collect();
+        assert!(ids.windows(2).all(|x| x[0] <= x[1]));
+        for vid in ids {
+            let vendor = builtin_vendor(vid).unwrap();
+            assert_eq!(vendor.id, vid);
+            assert!(!vendor.name.is_empty());
+            for device in vendor.devices.iter() {
+                assert_eq!(vendor.device(device.id).unwrap().name, device.name);
+                for subsystem in device.subsystems.iter() {
+                    let found = device.subsystem(subsystem.subdevice, subsystem.subvendor);
+                    assert_eq!(found.unwrap().name, subsystem.name);
+                }
+            }
+        }
+        for id in builtin_class_ids() {
+            let class = builtin_class(id).unwrap();
+            assert_eq!(class.id, id);
+            for subclass in class.subclasses.iter() {
+                assert_eq!(class.subclass(subclass.id).unwrap().name, subclass.name);
+                for prog in subclass.progs.iter() {
+                    assert_eq!(subclass.prog(prog.id).unwrap().name, prog.name);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,359 @@
+// Copyright (c) 2024 Gibson Pilconis, shibedrill, and contributors
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! # About this module
+//! These functions write the PCI IDs database out in formats other tools can
+//! read, so that they show the same names as libpci-rs. IDs are written as
+//! lowercase hex, the way pci.ids and `lspci -n` print them. To export the
+//! database lookups use, including any
+//! [runtime database](crate::ids::set_runtime_database) and
+//! [ID overrides](crate::ids::set_id_overrides):
+//! ```rust
+//! # use libpci_rs::class::all_classes;
+//! # use libpci_rs::ids::all_vendors;
+//! # use libpci_rs::ids::export::{export, ExportFormat};
+//! let mut csv = Vec::new();
+//! export(&mut csv, ExportFormat::Csv, all_vendors(), all_classes()).unwrap();
+//! assert!(csv.starts_with(b"vendor,device,subvendor,subdevice,name\n"));
+//! ```
+//! The `pciids-export` binary does the same from the command line.
+
+use std::io::{self, Write};
+
+use crate::class::PciClassEntry;
+use crate::ids::PciVendorEntry;
+
+/// A list of formats the database can be exported to.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// A JSON object with `vendors` and `classes` arrays, holding their
+    /// devices, subsystems, subclasses and programming interfaces as nested
+    /// arrays.
+    Json,
+    /// CSV with `vendor,device,subvendor,subdevice,name` columns and a row for
+    /// every vendor, device and subsystem. Columns that don't apply to a row
+    /// are left empty. Classes aren't included.
+    Csv,
+    /// SQL statements creating and filling a `pci_ids` table with the same
+    /// columns as the CSV, and a `pci_classes` table with
+    /// `class,subclass,prog_if,name` columns. Columns that don't apply to a
+    /// row are `NULL`.
+    Sql,
+}
+
+// The IDs of a vendor, device or subsystem: the vendor ID, then the device,
+// subvendor and subdevice IDs if the row has them.
+type IdRow = (u16, Option<u16>, Option<(u16, u16)>);
+
+// The IDs of a class, subclass or programming interface.
+type ClassRow = (u8, Option<u8>, Option<u8>);
+
+/// Writes vendors and classes out in the given format.
+pub fn export<W, V, C>(
+    mut writer: W,
+    format: ExportFormat,
+    vendors: V,
+    classes: C,
+) -> io::Result<()>
+where
+    W: Write,
+    V: IntoIterator<Item = PciVendorEntry>,
+    C: IntoIterator<Item = PciClassEntry>,
+{
+    match format {
+        ExportFormat::Json => export_json(&mut writer, vendors, classes),
+        ExportFormat::Csv => export_csv(&mut writer, vendors),
+        ExportFormat::Sql => export_sql(&mut writer, vendors, classes),
+    }?;
+    writer.flush()
+}
+
+fn export_json<W, V, C>(writer: &mut W, vendors: V, classes: C) -> io::Result<()>
+where
+    W: Write,
+    V: IntoIterator<Item = PciVendorEntry>,
+    C: IntoIterator<Item = PciClassEntry>,
+{
+    write!(writer, "{{\"vendors\":[")?;
+    for (index, vendor) in vendors.into_iter().enumerate() {
+        if index > 0 {
+            write!(writer, ",")?;
+        }
+        write!(
+            writer,
+            "\n{{\"id\":\"{:04x}\",\"name\":{},\"devices\":[",
+            vendor.id,
+            json_string(vendor.name)
+        )?;
+        for (index, device) in vendor.devices.iter().enumerate() {
+            if index > 0 {
+                write!(writer, ",")?;
+            }
+            write!(
+                writer,
+                "{{\"id\":\"{:04x}\",\"name\":{},\"subsystems\":[",
+                device.id,
+                json_string(device.name)
+            )?;
+            for (index, subsystem) in device.subsystems.iter().enumerate() {
+                if index > 0 {
+                    write!(writer, ",")?;
+                }
+                write!(
+                    writer,
+                    "{{\"subvendor\":\"{:04x}\",\"subdevice\":\"{:04x}\",\"name\":{}}}",
+                    subsystem.subvendor,
+                    subsystem.subdevice,
+                    json_string(subsystem.name)
+                )?;
+            }
+            write!(writer, "]}}")?;
+        }
+        write!(writer, "]}}")?;
+    }
+
+    write!(writer, "\n],\"classes\":[")?;
+    for (index, class) in classes.into_iter().enumerate() {
+        if index > 0 {
+            write!(writer, ",")?;
+        }
+        write!(
+            writer,
+            "\n{{\"id\":\"{:02x}\",\"name\":{},\"subclasses\":[",
+            class.id,
+            json_string(class.name)
+        )?;
+        for (index, subclass) in class.subclasses.iter().enumerate() {
+            if index > 0 {
+                write!(writer, ",")?;
+            }
+            write!(
+                writer,
+                "{{\"id\":\"{:02x}\",\"name\":{},\"progs\":[",
+                subclass.id,
+                json_string(subclass.name)
+            )?;
+            for (index, prog) in subclass.progs.iter().enumerate() {
+                if index > 0 {
+                    write!(writer, ",")?;
+                }
+                write!(
+                    writer,
+                    "{{\"id\":\"{:02x}\",\"name\":{}}}",
+                    prog.id,
+                    json_string(prog.name)
+                )?;
+            }
+            write!(writer, "]}}")?;
+        }
+        write!(writer, "]}}")?;
+    }
+    writeln!(writer, "\n]}}")
+}
+
+fn export_csv<W, V>(writer: &mut W, vendors: V) -> io::Result<()>
+where
+    W: Write,
+    V: IntoIterator<Item = PciVendorEntry>,
+{
+    writeln!(writer, "vendor,device,subvendor,subdevice,name")?;
+    for_each_id_row(vendors, |(vendor, device, subsystem), name| {
+        let hex = |id: Option<u16>| id.map(|x| format!("{:04x}", x)).unwrap_or_default();
+        writeln!(
+            writer,
+            "{:04x},{},{},{},{}",
+            vendor,
+            hex(device),
+            hex(subsystem.map(|x| x.0)),
+            hex(subsystem.map(|x| x.1)),
+            csv_field(name)
+        )
+    })
+}
+
+fn export_sql<W, V, C>(writer: &mut W, vendors: V, classes: C) -> io::Result<()>
+where
+    W: Write,
+    V: IntoIterator<Item = PciVendorEntry>,
+    C: IntoIterator<Item = PciClassEntry>,
+{
+    writeln!(
+        writer,
+        "CREATE TABLE IF NOT EXISTS pci_ids (vendor TEXT NOT NULL, device TEXT, subvendor TEXT, subdevice TEXT, name TEXT NOT NULL);"
+    )?;
+    writeln!(
+        writer,
+        "CREATE TABLE IF NOT EXISTS pci_classes (class TEXT NOT NULL, subclass TEXT, prog_if TEXT, name TEXT NOT NULL);"
+    )?;
+    writeln!(writer, "BEGIN TRANSACTION;")?;
+
+    for_each_id_row(vendors, |(vendor, device, subsystem), name| {
+        let hex = |id: Option<u16>| {
+            id.map(|x| format!("'{:04x}'", x))
+                .unwrap_or("NULL".to_string())
+        };
+        writeln!(
+            writer,
+            "INSERT INTO pci_ids VALUES ('{:04x}', {}, {}, {}, {});",
+            vendor,
+            hex(device),
+            hex(subsystem.map(|x| x.0)),
+            hex(subsystem.map(|x| x.1)),
+            sql_string(name)
+        )
+    })?;
+    for_each_class_row(classes, |(class, subclass, prog), name| {
+        let hex = |id: Option<u8>| {
+            id.map(|x| format!("'{:02x}'", x))
+                .unwrap_or("NULL".to_string())
+        };
+        writeln!(
+            writer,
+            "INSERT INTO pci_classes VALUES ('{:02x}', {}, {}, {});",
+            class,
+            hex(subclass),
+            hex(prog),
+            sql_string(name)
+        )
+    })?;
+
+    writeln!(writer, "COMMIT;")
+}
+
+// Calls `row` with the IDs and name of every vendor, device and subsystem,
+// each followed by its children.
+fn for_each_id_row<V, F>(vendors: V, mut row: F) -> io::Result<()>
+where
+    V: IntoIterator<Item = PciVendorEntry>,
+    F: FnMut(IdRow, &str) -> io::Result<()>,
+{
+    for vendor in vendors {
+        row((vendor.id, None, None), vendor.name)?;
+        for device in vendor.devices.iter() {
+            row((vendor.id, Some(device.id), None), device.name)?;
+            for subsystem in device.subsystems.iter() {
+                let ids = (subsystem.subvendor, subsystem.subdevice);
+                row((vendor.id, Some(device.id), Some(ids)), subsystem.name)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// Calls `row` with the IDs and name of every class, subclass and programming
+// interface, each followed by its children.
+fn for_each_class_row<C, F>(classes: C, mut row: F) -> io::Result<()>
+where
+    C: IntoIterator<Item = PciClassEntry>,
+    F: FnMut(ClassRow, &str) -> io::Result<()>,
+{
+    for class in classes {
+        row((class.id, None, None), class.name)?;
+        for subclass in class.subclasses.iter() {
+            row((class.id, Some(subclass.id), None), subclass.name)?;
+            for prog in subclass.progs.iter() {
+                row((class.id, Some(subclass.id), Some(prog.id)), prog.name)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn json_string(input: &str) -> String {
+    let mut output = String::with_capacity(input.len() + 2);
+    output.push('"');
+    for char in input.chars() {
+        match char {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            char if char < ' ' => output.push_str(&format!("\\u{:04x}", char as u32)),
+            char => output.push(char),
+        }
+    }
+    output.push('"');
+    output
+}
+
+// Quotes a CSV field if it contains anything that would break the row up.
+fn csv_field(input: &str) -> String {
+    if input.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", input.replace('"', "\"\""))
+    } else {
+        input.to_string()
+    }
+}
+
+fn sql_string(input: &str) -> String {
+    format!("'{}'", input.replace('\'', "''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ids::export::{export, ExportFormat};
+    use crate::ids::PciIdsDatabase;
+
+    const PCI_IDS: &str = "fffe  Vendor, \"Quoted\"\n\
+                           \t0001  Device's\n\
+                           \t\t0014 0002  Sub\\system\n\
+                           C 09  Class\n\
+                           \t7f  Subclass\n\
+                           \t\t01  Prog\n";
+
+    fn exported(format: ExportFormat) -> String {
+        let database: PciIdsDatabase = PCI_IDS.parse().unwrap();
+        let mut output = Vec::new();
+        export(&mut output, format, database.vendors(), database.classes()).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    /// Test exporting to JSON.
+    #[test]
+    fn test_export_json() {
+        assert_eq!(
+            exported(ExportFormat::Json),
+            "{\"vendors\":[\n\
+             {\"id\":\"fffe\",\"name\":\"Vendor, \\\"Quoted\\\"\",\"devices\":[\
+             {\"id\":\"0001\",\"name\":\"Device's\",\"subsystems\":[\
+             {\"subvendor\":\"0014\",\"subdevice\":\"0002\",\"name\":\"Sub\\\\system\"}]}]}\n\
+             ],\"classes\":[\n\
+             {\"id\":\"09\",\"name\":\"Class\",\"subclasses\":[\
+             {\"id\":\"7f\",\"name\":\"Subclass\",\"progs\":[{\"id\":\"01\",\"name\":\"Prog\"}]}]}\n\
+             ]}\n"
+        );
+    }
+
+    /// Test exporting to CSV.
+    #[test]
+    fn test_export_csv() {
+        assert_eq!(
+            exported(ExportFormat::Csv),
+            "vendor,device,subvendor,subdevice,name\n\
+             fffe,,,,\"Vendor, \"\"Quoted\"\"\"\n\
+             fffe,0001,,,Device's\n\
+             fffe,0001,0014,0002,Sub\\system\n"
+        );
+    }
+
+    /// Test exporting to SQL.
+    #[test]
+    fn test_export_sql() {
+        let sql = exported(ExportFormat::Sql);
+        let inserts: Vec<&str> = sql.lines().filter(|x| x.starts_with("INSERT")).collect();
+        assert_eq!(
+            inserts,
+            [
+                "INSERT INTO pci_ids VALUES ('fffe', NULL, NULL, NULL, 'Vendor, \"Quoted\"');",
+                "INSERT INTO pci_ids VALUES ('fffe', '0001', NULL, NULL, 'Device''s');",
+                "INSERT INTO pci_ids VALUES ('fffe', '0001', '0014', '0002', 'Sub\\system');",
+                "INSERT INTO pci_classes VALUES ('09', NULL, NULL, 'Class');",
+                "INSERT INTO pci_classes VALUES ('09', '7f', NULL, 'Subclass');",
+                "INSERT INTO pci_classes VALUES ('09', '7f', '01', 'Prog');",
+            ]
+        );
+        assert!(sql.starts_with("CREATE TABLE IF NOT EXISTS pci_ids"));
+        assert!(sql.ends_with("COMMIT;\n"));
+    }
+}
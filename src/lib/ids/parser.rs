@@ -0,0 +1,731 @@
+// Copyright (c) 2024 Gibson Pilconis, shibedrill, and contributors
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! # About this module
+//! A parser for the pci.ids file format, for tools that want to work with
+//! the database directly rather than through lookups. [`records`] reads a
+//! file line by line, and [`ingest_pciids`] assembles those lines into a
+//! [`ParsedIds`] tree, which can be turned into a
+//! [`PciIdsDatabase`](crate::ids::PciIdsDatabase).
+//! ```rust
+//! # use libpci_rs::ids::parser::ingest_pciids;
+//! let parsed = ingest_pciids("8086  Intel Corporation\n\t1237  440FX\n").unwrap();
+//! assert_eq!(parsed.vendors[0].devices[0].name, "440FX");
+//! ```
+
+// This parser is shared between the library, which uses it to load pci.ids
+// at runtime, and the build script, which uses it to compile pci.ids into
+// the library. The build script includes this file directly, so it may only
+// depend on the standard library.
+
+use std::fmt::{Display, Formatter};
+use std::iter::Enumerate;
+use std::str::Lines;
+
+/// A vendor parsed from pci.ids.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParsedVendor {
+    /// The integer vendor ID.
+    pub id: u16,
+    /// The name of the vendor.
+    pub name: String,
+    /// The devices listed under the vendor.
+    pub devices: Vec<ParsedDevice>,
+}
+
+/// A device parsed from pci.ids.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParsedDevice {
+    /// The integer device ID.
+    pub id: u16,
+    /// The name of the device.
+    pub name: String,
+    /// The subsystems listed under the device.
+    pub subsystems: Vec<ParsedSubsystem>,
+}
+
+/// A subsystem parsed from pci.ids.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParsedSubsystem {
+    /// The integer subvendor ID.
+    pub subvendor: u16,
+    /// The integer subdevice ID.
+    pub subdevice: u16,
+    /// The name of the subsystem.
+    pub name: String,
+}
+
+/// A device class parsed from pci.ids.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParsedClass {
+    /// The integer class ID.
+    pub id: u8,
+    /// The name of the class.
+    pub name: String,
+    /// The subclasses listed under the class.
+    pub subclasses: Vec<ParsedSubclass>,
+}
+
+/// A device subclass parsed from pci.ids.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParsedSubclass {
+    /// The integer subclass ID.
+    pub id: u8,
+    /// The name of the subclass.
+    pub name: String,
+    /// The programming interfaces listed under the subclass. Many subclasses
+    /// don't have any.
+    pub progs: Vec<ParsedProg>,
+}
+
+/// A programming interface parsed from pci.ids.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParsedProg {
+    /// The integer programming interface ID.
+    pub id: u8,
+    /// The name of the programming interface.
+    pub name: String,
+}
+
+/// An entry from a section of pci.ids that this parser doesn't know about.
+/// Sections other than vendors are introduced by a keyword, like `C` for
+/// classes, so entries of future sections are kept as text instead of being
+/// mistaken for vendors or classes.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParsedUnknown {
+    /// The keyword that introduced the section.
+    pub section: String,
+    /// The rest of the entry.
+    pub entry: String,
+    /// The lines indented under the entry, as pairs of indentation level and
+    /// text.
+    pub children: Vec<(usize, String)>,
+}
+
+/// Everything parsed from a pci.ids file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParsedIds {
+    /// The vendors, in the order they appear in the file.
+    pub vendors: Vec<ParsedVendor>,
+    /// The classes, in the order they appear in the file.
+    pub classes: Vec<ParsedClass>,
+    /// The entries of sections this parser doesn't know about.
+    pub unknown: Vec<ParsedUnknown>,
+    /// The version from the header of the file.
+    pub version: Option<String>,
+    /// The date from the header of the file.
+    pub date: Option<String>,
+}
+
+/// A single entry of a pci.ids file. Entries are parsed without their
+/// children, which follow as records of their own.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParsedRecord {
+    /// A vendor, at the top level.
+    Vendor(ParsedVendor),
+    /// A device, indented under a vendor.
+    Device(ParsedDevice),
+    /// A subsystem, indented under a device.
+    Subsystem(ParsedSubsystem),
+    /// A class, at the top level.
+    Class(ParsedClass),
+    /// A subclass, indented under a class.
+    Subclass(ParsedSubclass),
+    /// A programming interface, indented under a subclass.
+    Prog(ParsedProg),
+    /// An entry of an unknown section, at the top level.
+    Unknown(ParsedUnknown),
+    /// A line indented under an entry of an unknown section.
+    UnknownChild(usize, String),
+}
+
+/// An iterator over the records of a pci.ids file, made by [`records`].
+pub struct Records<'a> {
+    lines: Enumerate<Lines<'a>>,
+    section: Section,
+}
+
+impl ParsedIds {
+    /// Sorts every list of entries by ID, so that they can be binary
+    /// searched. Entries with the same ID keep their order.
+    pub fn sort(&mut self) {
+        self.vendors.sort_by_key(|x| x.id);
+        for vendor in &mut self.vendors {
+            vendor.devices.sort_by_key(|x| x.id);
+            for device in &mut vendor.devices {
+                device
+                    .subsystems
+                    .sort_by_key(|x| (x.subvendor, x.subdevice));
+            }
+        }
+
+        self.classes.sort_by_key(|x| x.id);
+        for class in &mut self.classes {
+            class.subclasses.sort_by_key(|x| x.id);
+            for subclass in &mut class.subclasses {
+                subclass.progs.sort_by_key(|x| x.id);
+            }
+        }
+    }
+
+    // Adds a record under the last entry it can belong to.
+    fn push(&mut self, record: ParsedRecord) -> Result<(), ParseErrorKind> {
+        let orphan = || ParseErrorKind::UnexpectedIndent;
+        match record {
+            ParsedRecord::Vendor(vendor) => self.vendors.push(vendor),
+            ParsedRecord::Device(device) => {
+                let vendor = self.vendors.last_mut().ok_or_else(orphan)?;
+                vendor.devices.push(device);
+            }
+            ParsedRecord::Subsystem(subsystem) => {
+                let vendor = self.vendors.last_mut().ok_or_else(orphan)?;
+                let device = vendor.devices.last_mut().ok_or_else(orphan)?;
+                device.subsystems.push(subsystem);
+            }
+            ParsedRecord::Class(class) => self.classes.push(class),
+            ParsedRecord::Subclass(subclass) => {
+                let class = self.classes.last_mut().ok_or_else(orphan)?;
+                class.subclasses.push(subclass);
+            }
+            ParsedRecord::Prog(prog) => {
+                let class = self.classes.last_mut().ok_or_else(orphan)?;
+                let subclass = class.subclasses.last_mut().ok_or_else(orphan)?;
+                subclass.progs.push(prog);
+            }
+            ParsedRecord::Unknown(unknown) => self.unknown.push(unknown),
+            ParsedRecord::UnknownChild(level, entry) => {
+                let unknown = self.unknown.last_mut().ok_or_else(orphan)?;
+                unknown.children.push((level, entry));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// An error encountered while parsing a pci.ids file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// The line the error was found on, starting from 1.
+    pub line: usize,
+    /// What went wrong.
+    pub kind: ParseErrorKind,
+}
+
+/// A list of problems that can be found on a line of a pci.ids file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// An ID is missing, isn't hexadecimal, or is out of range.
+    InvalidId,
+    /// An entry has an ID but no name.
+    MissingName,
+    /// An entry is indented without a parent to belong to, such as a device
+    /// before the first vendor, or is indented too deeply.
+    UnexpectedIndent,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "line {}: {}",
+            self.line,
+            match self.kind {
+                ParseErrorKind::InvalidId => "invalid ID",
+                ParseErrorKind::MissingName => "missing name",
+                ParseErrorKind::UnexpectedIndent => "unexpected indentation",
+            }
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+// The section of the file the last top-level entry belonged to, which decides
+// what indented entries are.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Section {
+    None,
+    Vendors,
+    Classes,
+    Unknown,
+}
+
+// Whether the first word of a top-level line introduces a section other than
+// vendors. Vendor IDs are four hex digits, so they can look like keywords.
+fn is_section_keyword(keyword: &str) -> bool {
+    let vendor_id = keyword.len() == 4 && keyword.bytes().all(|x| x.is_ascii_hexdigit());
+    !keyword.is_empty() && keyword.bytes().all(|x| x.is_ascii_uppercase()) && !vendor_id
+}
+
+// Splits an ID of `digits` hex digits off the front of an entry.
+fn id(input: &str, digits: usize) -> Result<(u32, &str), ParseErrorKind> {
+    let (id, rest) = input.split_once(char::is_whitespace).unwrap_or((input, ""));
+    // from_str_radix accepts a leading sign, so check the digits ourselves.
+    if id.len() != digits || !id.bytes().all(|x| x.is_ascii_hexdigit()) {
+        return Err(ParseErrorKind::InvalidId);
+    }
+    let id = u32::from_str_radix(id, 16).map_err(|_| ParseErrorKind::InvalidId)?;
+    Ok((id, rest))
+}
+
+// The name is whatever follows the IDs. pci.ids puts two spaces before it,
+// but hand-written files often use a tab or a single space.
+fn name(input: &str) -> Result<String, ParseErrorKind> {
+    match input.trim() {
+        "" => Err(ParseErrorKind::MissingName),
+        name => Ok(name.to_string()),
+    }
+}
+
+fn vendor(input: &str) -> Result<ParsedVendor, ParseErrorKind> {
+    let (id, rest) = id(input, 4)?;
+    Ok(ParsedVendor {
+        id: id as u16,
+        name: name(rest)?,
+        devices: vec![],
+    })
+}
+
+fn device(input: &str) -> Result<ParsedDevice, ParseErrorKind> {
+    let (id, rest) = id(input, 4)?;
+    Ok(ParsedDevice {
+        id: id as u16,
+        name: name(rest)?,
+        subsystems: vec![],
+    })
+}
+
+fn subsystem(input: &str) -> Result<ParsedSubsystem, ParseErrorKind> {
+    let (subvendor, rest) = id(input, 4)?;
+    let (subdevice, rest) = id(rest.trim_start(), 4)?;
+    Ok(ParsedSubsystem {
+        subvendor: subvendor as u16,
+        subdevice: subdevice as u16,
+        name: name(rest)?,
+    })
+}
+
+fn class(input: &str) -> Result<ParsedClass, ParseErrorKind> {
+    let (id, rest) = id(input, 2)?;
+    Ok(ParsedClass {
+        id: id as u8,
+        name: name(rest)?,
+        subclasses: vec![],
+    })
+}
+
+fn subclass(input: &str) -> Result<ParsedSubclass, ParseErrorKind> {
+    let (id, rest) = id(input, 2)?;
+    Ok(ParsedSubclass {
+        id: id as u8,
+        name: name(rest)?,
+        progs: vec![],
+    })
+}
+
+fn prog(input: &str) -> Result<ParsedProg, ParseErrorKind> {
+    let (id, rest) = id(input, 2)?;
+    Ok(ParsedProg {
+        id: id as u8,
+        name: name(rest)?,
+    })
+}
+
+impl Records<'_> {
+    // Parses one non-comment line. `level` is the number of tabs the line was
+    // indented by, and `entry` is the rest of the line.
+    fn record(&mut self, level: usize, entry: &str) -> Result<ParsedRecord, ParseErrorKind> {
+        if level == 0 {
+            let (keyword, rest) = entry.split_once(char::is_whitespace).unwrap_or((entry, ""));
+            if keyword == "C" {
+                self.section = Section::Classes;
+                return class(rest.trim_start()).map(ParsedRecord::Class);
+            }
+            if is_section_keyword(keyword) {
+                self.section = Section::Unknown;
+                return Ok(ParsedRecord::Unknown(ParsedUnknown {
+                    section: keyword.to_string(),
+                    entry: rest.trim_start().to_string(),
+                    children: vec![],
+                }));
+            }
+            self.section = Section::Vendors;
+            return vendor(entry).map(ParsedRecord::Vendor);
+        }
+
+        match (level, self.section) {
+            (1, Section::Vendors) => device(entry).map(ParsedRecord::Device),
+            (2, Section::Vendors) => subsystem(entry).map(ParsedRecord::Subsystem),
+            (1, Section::Classes) => subclass(entry).map(ParsedRecord::Subclass),
+            (2, Section::Classes) => prog(entry).map(ParsedRecord::Prog),
+            (_, Section::Unknown) => Ok(ParsedRecord::UnknownChild(level, entry.to_string())),
+            _ => Err(ParseErrorKind::UnexpectedIndent),
+        }
+    }
+}
+
+impl Iterator for Records<'_> {
+    /// Each record comes with the line it was found on, starting from 1.
+    type Item = Result<(usize, ParsedRecord), ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // lines() also strips the carriage return of CRLF line endings.
+        for (index, line) in self.lines.by_ref() {
+            let entry = line.trim_start_matches('\t');
+            let level = line.len() - entry.len();
+            let entry = entry.trim_end();
+            // Skip comments and blank lines.
+            if entry.is_empty() || entry.trim_start().starts_with('#') {
+                continue;
+            }
+            let line = index + 1;
+            return Some(
+                self.record(level, entry)
+                    .map(|record| (line, record))
+                    .map_err(|kind| ParseError { line, kind }),
+            );
+        }
+        None
+    }
+}
+
+/// Reads the entries of a pci.ids file one line at a time, skipping comments
+/// and blank lines. Both LF and CRLF line endings are accepted. Unlike
+/// [`ingest_pciids`], reading carries on after a line that can't be parsed.
+pub fn records(pciids_raw: &str) -> Records<'_> {
+    Records {
+        lines: pciids_raw.lines().enumerate(),
+        section: Section::None,
+    }
+}
+
+/// Parses the contents of a pci.ids file, stopping at the first line that
+/// can't be parsed. Both LF and CRLF line endings are accepted.
+pub fn ingest_pciids(pciids_raw: &str) -> Result<ParsedIds, ParseError> {
+    // The header comment holds lines like "#\tVersion: 2024.11.22".
+    let header = |key: &str| {
+        pciids_raw
+            .lines()
+            .take_while(|str| str.starts_with('#'))
+            .find_map(|str| str.trim_start_matches('#').trim().strip_prefix(key))
+            .map(|value| value.trim().to_string())
+    };
+
+    let mut result = ParsedIds {
+        version: header("Version:"),
+        date: header("Date:"),
+        ..Default::default()
+    };
+    for record in records(pciids_raw) {
+        let (line, record) = record?;
+        result
+            .push(record)
+            .map_err(|kind| ParseError { line, kind })?;
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PCI_IDS: &str = "# Version: 2024.11.22\n\
+                           fffe  Test Vendor\n\
+                           \t0001  Test Device\n\
+                           \t\t0014 0001  Test Subsystem\n\
+                           C 09  Test Class\n\
+                           \t7f  Test Subclass\n\
+                           \t\t01  Test Prog\n";
+
+    fn error(input: &str) -> (usize, ParseErrorKind) {
+        let err = ingest_pciids(input).unwrap_err();
+        (err.line, err.kind)
+    }
+
+    /// Test reporting malformed lines with their line numbers.
+    #[test]
+    fn test_parse_errors() {
+        use ParseErrorKind::*;
+        assert_eq!(error("# Comment\n\nfffg  Vendor\n"), (3, InvalidId));
+        assert_eq!(error("+fff  Vendor\n"), (1, InvalidId));
+        assert_eq!(error("fffe  Vendor\n\t00001  Device\n"), (2, InvalidId));
+        assert_eq!(
+            error("fffe  Vendor\n\t0001  Device\n\t\t0014  Sub\n"),
+            (3, InvalidId)
+        );
+        assert_eq!(error("C 100  Class\n"), (1, InvalidId));
+        assert_eq!(error("fffe\n"), (1, MissingName));
+        assert_eq!(error("fffe  Vendor\n\t0001   \n"), (2, MissingName));
+        assert_eq!(error("\t0001  Device\n"), (1, UnexpectedIndent));
+        assert_eq!(
+            error("fffe  Vendor\n\t\t0014 0001  Sub\n"),
+            (2, UnexpectedIndent)
+        );
+        assert_eq!(error("C 09  Class\n\t\t01  Prog\n"), (2, UnexpectedIndent));
+        assert_eq!(
+            error("fffe  Vendor\n\t0001  Device\n\t\t\t0001  Deep\n"),
+            (3, UnexpectedIndent)
+        );
+        assert_eq!(
+            ingest_pciids("\n\nfffe\n").unwrap_err().to_string(),
+            "line 3: missing name"
+        );
+    }
+
+    /// Test tolerating CRLF line endings and stray whitespace.
+    #[test]
+    fn test_line_endings_and_whitespace() {
+        let expected = ingest_pciids(PCI_IDS).unwrap();
+        assert_eq!(
+            expected.vendors[0].devices[0].subsystems[0].name,
+            "Test Subsystem"
+        );
+        assert_eq!(expected.classes[0].subclasses[0].progs[0].name, "Test Prog");
+        assert_eq!(expected.version.as_deref(), Some("2024.11.22"));
+
+        let crlf = PCI_IDS.replace('\n', " \t\r\n");
+        assert_eq!(ingest_pciids(&crlf).unwrap(), expected);
+
+        let parsed = ingest_pciids("fffe\tTab\tVendor\t\r\n\t0001 One  Space\r\n").unwrap();
+        assert_eq!(parsed.vendors[0].name, "Tab\tVendor");
+        assert_eq!(parsed.vendors[0].devices[0].name, "One  Space");
+    }
+
+    /// Test parsing prog-if-less subclasses and unknown sections.
+    #[test]
+    fn test_sections() {
+        let input = "C 00  Unclassified device\n\
+                     \t00  Non-VGA unclassified device\n\
+                     \t01  VGA compatible unclassified device\n\
+                     \t\t00  VGA\n\
+                     R 01  Future section\n\
+                     \t02  Future child\n\
+                     \t\t03  Future grandchild\n\
+                     ABCD  Hex Vendor\n\
+                     \t0001  Hex Device\n";
+        let parsed = ingest_pciids(input).unwrap();
+
+        // A subclass without programming interfaces is followed by one with.
+        let subclasses = &parsed.classes[0].subclasses;
+        assert!(subclasses[0].progs.is_empty());
+        assert_eq!(subclasses[1].progs[0].name, "VGA");
+
+        // The unknown section's children aren't mistaken for subclasses.
+        assert_eq!(parsed.classes.len(), 1);
+        assert_eq!(subclasses.len(), 2);
+        assert_eq!(
+            parsed.unknown,
+            vec![ParsedUnknown {
+                section: "R".to_string(),
+                entry: "01  Future section".to_string(),
+                children: vec![
+                    (1, "02  Future child".to_string()),
+                    (2, "03  Future grandchild".to_string())
+                ],
+            }]
+        );
+
+        // A vendor ID made of letters still starts a vendor.
+        assert_eq!(parsed.vendors[0].id, 0xabcd);
+        assert_eq!(parsed.vendors[0].devices[0].name, "Hex Device");
+    }
+
+    /// Test iterating over records, continuing past errors.
+    #[test]
+    fn test_records() {
+        let records: Vec<_> = records("fffe  Vendor\n\t0001\n\n\t0002  Device\n").collect();
+        assert_eq!(records.len(), 3);
+        assert!(matches!(&records[0], Ok((1, ParsedRecord::Vendor(x))) if x.id == 0xfffe));
+        assert_eq!(
+            records[1],
+            Err(ParseError {
+                line: 2,
+                kind: ParseErrorKind::MissingName
+            })
+        );
+        assert!(matches!(&records[2], Ok((4, ParsedRecord::Device(x))) if x.name == "Device"));
+    }
+
+    /// Test parsing the pci.ids the library is built from, if there is one.
+    #[test]
+    fn test_upstream_file() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/pciids/pci.ids");
+        let Ok(input) = std::fs::read_to_string(path) else {
+            return;
+        };
+        let parsed = ingest_pciids(&input).unwrap();
+        assert!(parsed.version.is_some());
+        assert!(parsed.unknown.is_empty());
+
+        // Every entry of the file ends up somewhere in the tree.
+        let entries = input
+            .lines()
+            .filter(|x| !x.trim().is_empty() && !x.trim_start().starts_with('#'))
+            .count();
+        let mut count = parsed.vendors.len() + parsed.classes.len();
+        for vendor in &parsed.vendors {
+            count += vendor.devices.len();
+            count += vendor
+                .devices
+                .iter()
+                .map(|x| x.subsystems.len())
+                .sum::<usize>();
+        }
+        for class in &parsed.classes {
+            count += class.subclasses.len();
+            count += class
+                .subclasses
+                .iter()
+                .map(|x| x.progs.len())
+                .sum::<usize>();
+        }
+        assert_eq!(count, entries);
+
+        let intel = parsed.vendors.iter().find(|x| x.id == 0x8086).unwrap();
+        assert_eq!(intel.name, "Intel Corporation");
+        let serial = parsed.classes.iter().find(|x| x.id == 0x0c).unwrap();
+        let usb = serial.subclasses.iter().find(|x| x.id == 0x03).unwrap();
+        assert!(usb.progs.iter().any(|x| x.id == 0x30 && x.name == "XHCI"));
+        assert!(parsed.classes[0].subclasses[0].progs.is_empty());
+    }
+
+    // Generates a name that survives a round trip: no whitespace at either
+    // end, and not mistaken for a comment.
+    fn random_name(rng: &mut fastrand::Rng) -> String {
+        const CHARS: &[u8] = b"abcXYZ019#[]()/-. \t";
+        let mut name = String::from(rng.alphanumeric());
+        for _ in 0..rng.usize(0..12) {
+            name.push(CHARS[rng.usize(..CHARS.len())] as char);
+        }
+        name.push(rng.alphanumeric());
+        name
+    }
+
+    fn random_ids(rng: &mut fastrand::Rng) -> ParsedIds {
+        let mut ids = ParsedIds::default();
+        for _ in 0..rng.usize(0..4) {
+            ids.vendors.push(ParsedVendor {
+                id: rng.u16(..),
+                name: random_name(rng),
+                devices: (0..rng.usize(0..4))
+                    .map(|_| ParsedDevice {
+                        id: rng.u16(..),
+                        name: random_name(rng),
+                        subsystems: (0..rng.usize(0..3))
+                            .map(|_| ParsedSubsystem {
+                                subvendor: rng.u16(..),
+                                subdevice: rng.u16(..),
+                                name: random_name(rng),
+                            })
+                            .collect(),
+                    })
+                    .collect(),
+            });
+        }
+        for _ in 0..rng.usize(0..4) {
+            ids.classes.push(ParsedClass {
+                id: rng.u8(..),
+                name: random_name(rng),
+                subclasses: (0..rng.usize(0..4))
+                    .map(|_| ParsedSubclass {
+                        id: rng.u8(..),
+                        name: random_name(rng),
+                        progs: (0..rng.usize(0..3))
+                            .map(|_| ParsedProg {
+                                id: rng.u8(..),
+                                name: random_name(rng),
+                            })
+                            .collect(),
+                    })
+                    .collect(),
+            });
+        }
+        ids
+    }
+
+    // Writes the entries out as pci.ids, with random line endings, trailing
+    // whitespace, separators, comments and blank lines.
+    fn render(rng: &mut fastrand::Rng, ids: &ParsedIds) -> String {
+        let mut lines = vec![];
+        for vendor in &ids.vendors {
+            lines.push(format!("{:04x}", vendor.id) + "  " + &vendor.name);
+            for device in &vendor.devices {
+                lines.push(format!("\t{:04X}", device.id) + "  " + &device.name);
+                for subsystem in &device.subsystems {
+                    lines.push(format!(
+                        "\t\t{:04x} {:04x}  {}",
+                        subsystem.subvendor, subsystem.subdevice, subsystem.name
+                    ));
+                }
+            }
+        }
+        for class in &ids.classes {
+            lines.push(format!("C {:02x}  {}", class.id, class.name));
+            for subclass in &class.subclasses {
+                lines.push(format!("\t{:02x}  {}", subclass.id, subclass.name));
+                for prog in &subclass.progs {
+                    lines.push(format!("\t\t{:02x}  {}", prog.id, prog.name));
+                }
+            }
+        }
+
+        let mut output = String::new();
+        for line in lines {
+            if rng.u8(..8) == 0 {
+                output.push_str(["\n", "# Comment\n", "\t# Comment\r\n"][rng.usize(..3)]);
+            }
+            output.push_str(&line);
+            output.push_str(["", " ", "\t", "  \t"][rng.usize(..4)]);
+            output.push_str(if rng.bool() { "\r\n" } else { "\n" });
+        }
+        output
+    }
+
+    /// Test parsing randomly generated databases back to what generated them.
+    #[test]
+    fn test_round_trip() {
+        let mut rng = fastrand::Rng::with_seed(0x9c1);
+        for _ in 0..500 {
+            let ids = random_ids(&mut rng);
+            let rendered = render(&mut rng, &ids);
+            assert_eq!(
+                ingest_pciids(&rendered).as_ref(),
+                Ok(&ids),
+                "{:?}",
+                rendered
+            );
+        }
+    }
+
+    /// Test that mangled input is parsed or rejected without panicking.
+    #[test]
+    fn test_fuzz() {
+        const CHARS: &[char] = &['\t', ' ', '\r', '\n', '#', 'C', '0', 'f', 'g', '-', 'é'];
+        let mut rng = fastrand::Rng::with_seed(0x5eed);
+        let base = ingest_pciids(PCI_IDS).unwrap();
+        let rendered = render(&mut rng, &base);
+        for _ in 0..2000 {
+            let mut input: Vec<char> = rendered.chars().collect();
+            for _ in 0..rng.usize(1..8) {
+                let index = rng.usize(..input.len());
+                match rng.u8(..3) {
+                    0 => input[index] = CHARS[rng.usize(..CHARS.len())],
+                    1 => input.insert(index, CHARS[rng.usize(..CHARS.len())]),
+                    _ => {
+                        input.remove(index);
+                    }
+                }
+                if input.is_empty() {
+                    break;
+                }
+            }
+            let input: String = input.into_iter().collect();
+            // Whatever the input, parsing must return instead of panicking,
+            // and errors must point at a line that exists.
+            if let Err(err) = ingest_pciids(&input) {
+                assert!(err.line >= 1 && err.line <= input.lines().count());
+            }
+        }
+    }
+}
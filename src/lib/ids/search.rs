@@ -0,0 +1,148 @@
+// Copyright (c) 2024 Gibson Pilconis, shibedrill, and contributors
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! # About this module
+//! These functions find the vendors, devices and subsystems whose names
+//! match a query, which is useful when you know what a device is called but
+//! not its IDs. Matches are returned as [`PciIdsMatch`] tuples:
+//! ```rust
+//! # use libpci_rs::ids::search;
+//! for (vendor_id, device_id, name) in search::fuzzy("RTX 4090").into_iter().take(5) {
+//!     println!("{:04x}:{:04x?} {}", vendor_id, device_id, name);
+//! }
+//! ```
+//! The [runtime database](crate::ids::set_runtime_database) and
+//! [ID overrides](crate::ids::set_id_overrides) are searched as well.
+
+use crate::ids::all_vendors;
+
+/// A vendor, device or subsystem whose name matched a query, as a
+/// `(vendor_id, device_id, name)` tuple. The device ID is [`None`] for
+/// vendors. Subsystems are reported with the IDs of the device they belong
+/// to.
+pub type PciIdsMatch = (u16, Option<u16>, &'static str);
+
+/// Finds the names containing `query`, ignoring case.
+pub fn substring(query: &str) -> Vec<PciIdsMatch> {
+    let query = query.to_lowercase();
+    search_by(|name| name.to_lowercase().contains(&query).then_some(0))
+}
+
+/// Finds the names matching a regular expression. Depends on the
+/// [regex](crate#the-regex-feature) feature.
+#[cfg(feature = "regex")]
+pub fn regex(regex: &regex::Regex) -> Vec<PciIdsMatch> {
+    search_by(|name| regex.is_match(name).then_some(0))
+}
+
+/// Finds the names containing the characters of `query` in order, ignoring
+/// case and whitespace, best matches first. Names matching the query as a
+/// whole, or at the start of words, rank higher.
+pub fn fuzzy(query: &str) -> Vec<PciIdsMatch> {
+    let query: Vec<char> = query
+        .to_lowercase()
+        .chars()
+        .filter(|x| !x.is_whitespace())
+        .collect();
+    if query.is_empty() {
+        return Vec::new();
+    }
+    search_by(|name| fuzzy_score(&query, name))
+}
+
+// Scores a name against a query, or returns None if the query isn't a
+// subsequence of the name.
+fn fuzzy_score(query: &[char], name: &str) -> Option<i64> {
+    let name: Vec<char> = name.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut position = 0;
+    let mut previous: Option<usize> = None;
+
+    for wanted in query {
+        let found = position + name[position..].iter().position(|x| x == wanted)?;
+        score += 1;
+        if found > 0 && previous == Some(found - 1) {
+            // Consecutive characters.
+            score += 5;
+        }
+        if found == 0 || !name[found - 1].is_alphanumeric() {
+            // Start of a word.
+            score += 3;
+        }
+        previous = Some(found);
+        position = found + 1;
+    }
+
+    // Prefer names that don't have much besides the query.
+    Some(score * 16 - (name.len() - query.len()) as i64)
+}
+
+// Collects the entries whose names get a score, best scores first.
+fn search_by<F>(score: F) -> Vec<PciIdsMatch>
+where
+    F: Fn(&str) -> Option<i64>,
+{
+    let mut result: Vec<(i64, PciIdsMatch)> = Vec::new();
+
+    for vendor in all_vendors() {
+        if let Some(x) = score(vendor.name()) {
+            result.push((x, (vendor.id(), None, vendor.name())));
+        }
+        for device in vendor.devices().unwrap_or_default() {
+            if let Some(x) = score(device.name()) {
+                result.push((x, (vendor.id(), Some(device.id()), device.name())));
+            }
+            for subsystem in device.subsystems().unwrap_or_default() {
+                if let Some(x) = score(subsystem.name()) {
+                    result.push((x, (vendor.id(), Some(device.id()), subsystem.name())));
+                }
+            }
+        }
+    }
+
+    // Stable, so entries with the same score stay sorted by ID.
+    result.sort_by_key(|(x, _)| -x);
+    result.into_iter().map(|(_, x)| x).collect()
+}
+
+// The tests look up devices, which the trimming features leave out.
+#[cfg(all(
+    test,
+    not(any(feature = "pciids-vendors-only", feature = "pciids-no-subsystems"))
+))]
+mod tests {
+    use super::*;
+
+    /// Test finding names by substring.
+    #[test]
+    fn test_substring() {
+        let matches = substring("loongson");
+        assert!(matches.contains(&(0x0014, None, "Loongson Technology LLC")));
+
+        let matches = substring("NVS 4200M");
+        assert!(matches.contains(&(0x10de, Some(0x1056), "GF119M [NVS 4200M]")));
+        assert!(substring("no such device name").is_empty());
+    }
+
+    /// Test finding names by regular expression.
+    #[cfg(feature = "regex")]
+    #[test]
+    fn test_regex() {
+        let matches = regex(&regex::Regex::new(r"^GF119M \[NVS \d+M\]$").unwrap());
+        assert!(matches.contains(&(0x10de, Some(0x1056), "GF119M [NVS 4200M]")));
+    }
+
+    /// Test ranking fuzzy matches.
+    #[test]
+    fn test_fuzzy() {
+        let matches = fuzzy("nvs4200");
+        assert_eq!(matches[0], (0x10de, Some(0x1056), "GF119M [NVS 4200M]"));
+
+        // Whole words rank above scattered characters.
+        assert!(
+            fuzzy_score(&['w', 'i', 'f', 'i'], "Wi-Fi 6 AX201")
+                > fuzzy_score(&['w', 'i', 'f', 'i'], "Wireless Interface Fabric")
+        );
+        assert!(fuzzy_score(&['x', 'y', 'z'], "Wi-Fi 6 AX201").is_none());
+    }
+}
@@ -28,14 +28,65 @@
 //! whether it's installed or not. Because this feature requires compiling in
 //! the PCIIDs database, it will increase the size of the library. It is
 //! enabled by default.
+//!
+//! The database is compiled from the `pciids` submodule. If it is not
+//! initialized, the copy installed by the system's hwdata package is used
+//! instead. Packagers can also point the build at a specific copy by setting
+//! the `LIBPCI_RS_PCI_IDS` environment variable to its path.
+//!
+//! # The `gzip` feature
+//! The `gzip` feature allows loading gzip-compressed copies of pci.ids at
+//! runtime, such as the `/usr/share/misc/pci.ids.gz` shipped by many
+//! distributions. It also lets the build script compile in the database from
+//! `pciids/pci.ids.gz` when `pciids/pci.ids` is not present. It implies the
+//! [pciids](#the-pciids-feature) feature.
+//!
+//! # The `pciids-compact` feature
+//! The `pciids-compact` feature compiles the PCI IDs database into a compact
+//! binary blob instead of Rust data structures, which makes the library
+//! smaller and quicker to compile. The entries of a vendor or class are
+//! decoded the first time they are looked up, so lookups are slightly slower
+//! the first time. The API is the same either way. It implies the
+//! [pciids](#the-pciids-feature) feature.
+//!
+//! # Trimming the database
+//! Applications that only deal with some devices can leave the rest of the
+//! PCI IDs database out of the library:
+//! - The `pciids-no-subsystems` feature leaves out subsystems.
+//! - The `pciids-vendors-only` feature leaves out devices and subsystems,
+//!   keeping only vendor and class names.
+//! - Setting the `LIBPCI_RS_PCIIDS_VENDORS` environment variable at build
+//!   time to a comma-separated list of hexadecimal vendor IDs, like
+//!   `8086,15b3,14e4`, leaves out every other vendor.
+//!
+//! The [runtime database](crate::ids::set_runtime_database) is not affected.
+//!
+//! # The `regex` feature
+//! The `regex` feature allows searching the names in the PCI IDs database
+//! with regular expressions. It implies the [pciids](#the-pciids-feature)
+//! feature.
 
 use cfg_if::cfg_if;
 
 /// The platform-dependent backend modules responsible for handling platform
 /// specific syscalls, parsing, and error handling.
 mod backend;
+/// Structures and functions related to decoding the configuration space of
+/// PCI devices and their capabilities.
+pub mod config;
 /// Structures and functions related to enumerating PCI devices.
 pub mod pci;
+/// Structures and functions related to recording the PCI devices in a system
+/// and comparing recordings.
+pub mod snapshot;
+
+cfg_if! {
+    if #[cfg(any(target_os = "linux", target_os = "android"))] {
+        /// Linux-specific device information and control through sysfs and
+        /// procfs. Only available on Linux and Android.
+        pub mod linux;
+    }
+}
 
 cfg_if! {
     if #[cfg(feature = "pciids")] {
@@ -45,5 +96,11 @@ cfg_if! {
         /// Structures and functions related to PCI device class
         /// classifications. Depends on the [pciids](#pciids) feature.
         pub mod class;
+        /// Formatting device names the way pciutils does. Depends on the
+        /// [pciids](#pciids) feature.
+        pub mod names;
+        /// Formatting devices like the machine-readable output of pciutils'
+        /// `lspci`. Depends on the [pciids](#pciids) feature.
+        pub mod machine;
     }
 }
@@ -0,0 +1,153 @@
+// Copyright (c) 2024 Gibson Pilconis, shibedrill, and contributors
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! # About this module
+//! Latency-sensitive software usually wants to run next to the hardware it
+//! talks to. This module tells you which NUMA node and CPUs are local to a
+//! device, which interrupts it uses, and which CPUs those interrupts are
+//! allowed to fire on:
+//! ```rust
+//! # use libpci_rs::pci::get_pci_list;
+//! for device in get_pci_list().unwrap() {
+//!     let Some(address) = device.address else { continue };
+//!     println!("{}: local CPUs {:?}", address, address.local_cpulist());
+//!     for irq in address.msi_irqs().unwrap_or_default() {
+//!         println!("  IRQ {} ({:?}) -> {:?}", irq.number, irq.kind, irq.affinity);
+//!     }
+//! }
+//! ```
+
+use crate::linux::{parse_cpulist, parse_cpumask, parse_decimal, read_attribute, read_trimmed};
+use crate::pci::{PciDeviceAddress, PciEnumerationError};
+
+use std::fs::read_dir;
+use std::path::Path;
+
+/// The kind of interrupt a device uses.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PciIrqKind {
+    /// A legacy INTx interrupt.
+    Legacy,
+    /// A Message Signaled Interrupt.
+    Msi,
+    /// An extended Message Signaled Interrupt.
+    MsiX,
+}
+
+/// An interrupt allocated to a device.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct PciIrq {
+    /// The Linux IRQ number.
+    pub number: u32,
+    /// The kind of interrupt.
+    pub kind: PciIrqKind,
+    /// The CPUs the interrupt may be delivered to, from
+    /// `/proc/irq/N/smp_affinity_list`. This is [`None`] if procfs does not
+    /// list the interrupt or it can't be read.
+    pub affinity: Option<Vec<u32>>,
+}
+
+/// Internal function to get the CPUs an IRQ may be delivered to.
+fn irq_affinity(number: u32) -> Option<Vec<u32>> {
+    let path = Path::new("/proc/irq")
+        .join(number.to_string())
+        .join("smp_affinity_list");
+    parse_cpulist(&read_trimmed(&path).ok()?).ok()
+}
+
+impl PciDeviceAddress {
+    /// Gets the NUMA node the device is attached to. Returns [`None`] if the
+    /// kernel is built without NUMA support or the firmware does not report a
+    /// node.
+    pub fn numa_node(&self) -> Result<Option<u32>, PciEnumerationError> {
+        let node: i32 = match read_attribute(self, "numa_node") {
+            Ok(node) => parse_decimal(&node)?,
+            Err(PciEnumerationError::NotFound) => return Ok(None),
+            Err(err) => return Err(err),
+        };
+        // The kernel reports -1 when the node is unknown.
+        Ok(u32::try_from(node).ok())
+    }
+
+    /// Gets the CPUs local to the device, from the `local_cpulist`
+    /// attribute.
+    pub fn local_cpulist(&self) -> Result<Vec<u32>, PciEnumerationError> {
+        parse_cpulist(&read_attribute(self, "local_cpulist")?)
+    }
+
+    /// Gets the CPUs local to the device, from the `local_cpus` attribute.
+    /// This holds the same information as [`local_cpulist`](Self::local_cpulist),
+    /// written as a hex mask.
+    pub fn local_cpus(&self) -> Result<Vec<u32>, PciEnumerationError> {
+        parse_cpumask(&read_attribute(self, "local_cpus")?)
+    }
+
+    /// Gets the legacy INTx interrupt of the device, along with its affinity.
+    /// Returns [`None`] if the device has no legacy interrupt assigned, or
+    /// the kernel does not report one.
+    pub fn irq(&self) -> Result<Option<PciIrq>, PciEnumerationError> {
+        let number: u32 = match read_attribute(self, "irq") {
+            Ok(number) => parse_decimal(&number)?,
+            Err(PciEnumerationError::NotFound) => return Ok(None),
+            Err(err) => return Err(err),
+        };
+        if number == 0 {
+            return Ok(None);
+        }
+        Ok(Some(PciIrq {
+            number,
+            kind: PciIrqKind::Legacy,
+            affinity: irq_affinity(number),
+        }))
+    }
+
+    /// Gets the MSI and MSI-X interrupts currently allocated to the device,
+    /// sorted by IRQ number, from the `msi_irqs` directory. Devices without a
+    /// driver usually have none, in which case the list is empty.
+    pub fn msi_irqs(&self) -> Result<Vec<PciIrq>, PciEnumerationError> {
+        let directory = self.sysfs_path().join("msi_irqs");
+        if !directory.exists() {
+            return Ok(vec![]);
+        }
+
+        let mut irqs: Vec<PciIrq> = Vec::new();
+        for entry in read_dir(directory)? {
+            let entry = entry?;
+            let number: u32 = parse_decimal(&entry.file_name().to_string_lossy())?;
+            let kind = match read_trimmed(&entry.path())?.as_str() {
+                "msix" => PciIrqKind::MsiX,
+                _ => PciIrqKind::Msi,
+            };
+            irqs.push(PciIrq {
+                number,
+                kind,
+                affinity: irq_affinity(number),
+            });
+        }
+
+        irqs.sort();
+        Ok(irqs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    /// Test reading the affinity information of every device in the system.
+    #[test]
+    fn test_device_affinity() {
+        println!("Begin test output: test_device_affinity");
+        let device_list = crate::pci::get_pci_list().unwrap();
+        for device in device_list {
+            let address = device.address.unwrap();
+            println!(
+                "{} node={:?} cpus={:?} irq={:?} msi={:?}",
+                address,
+                address.numa_node().unwrap(),
+                address.local_cpulist().unwrap(),
+                address.irq().unwrap(),
+                address.msi_irqs().unwrap()
+            );
+        }
+        println!("End test output: test_device_affinity");
+    }
+}
@@ -0,0 +1,186 @@
+// Copyright (c) 2024 Gibson Pilconis, shibedrill, and contributors
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! # About this module
+//! The kernel decides which ASPM states to enable from a global policy, but
+//! newer kernels also let you override each state per link through the
+//! files in the `link` directory of the device at the downstream end of the
+//! link. This module reads and changes both. Changing anything requires
+//! root:
+//! ```rust
+//! # use libpci_rs::linux::aspm::aspm_policy;
+//! # use libpci_rs::pci::get_pci_list;
+//! println!("ASPM policy: {:?}", aspm_policy());
+//! for device in get_pci_list().unwrap() {
+//!     let Some(address) = device.address else { continue };
+//!     println!("{}: {:?}", address, address.aspm_link_states());
+//! }
+//! ```
+
+use crate::linux::{parse_bool, read_attribute, read_trimmed, write_attribute, write_value};
+use crate::pci::{PciDeviceAddress, PciEnumerationError};
+
+use std::fmt::{self, Display, Formatter};
+use std::path::Path;
+use std::str::FromStr;
+
+/// The file holding the global ASPM policy.
+const ASPM_POLICY: &str = "/sys/module/pcie_aspm/parameters/policy";
+
+/// The global ASPM policy of the kernel.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PciAspmPolicy {
+    /// Use the states the firmware configured.
+    Default,
+    /// Disable ASPM.
+    Performance,
+    /// Enable L0s and L1.
+    PowerSave,
+    /// Enable L0s, L1, and the L1 substates.
+    PowerSuperSave,
+}
+
+/// A link state that can be toggled per link through sysfs.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PciAspmLinkState {
+    /// ASPM L0s, from `link/l0s_aspm`.
+    L0s,
+    /// ASPM L1, from `link/l1_aspm`.
+    L1,
+    /// ASPM L1.1, from `link/l1_1_aspm`.
+    L1_1,
+    /// ASPM L1.2, from `link/l1_2_aspm`.
+    L1_2,
+    /// PCI-PM L1.1, from `link/l1_1_pcipm`.
+    L1_1PciPm,
+    /// PCI-PM L1.2, from `link/l1_2_pcipm`.
+    L1_2PciPm,
+    /// Clock power management, from `link/clkpm`.
+    ClockPm,
+}
+
+impl PciAspmLinkState {
+    /// All of the link states, in the order they are listed in.
+    pub const ALL: [PciAspmLinkState; 7] = [
+        Self::L0s,
+        Self::L1,
+        Self::L1_1,
+        Self::L1_2,
+        Self::L1_1PciPm,
+        Self::L1_2PciPm,
+        Self::ClockPm,
+    ];
+
+    /// Returns the sysfs attribute controlling the link state.
+    fn attribute(&self) -> &'static str {
+        match self {
+            Self::L0s => "link/l0s_aspm",
+            Self::L1 => "link/l1_aspm",
+            Self::L1_1 => "link/l1_1_aspm",
+            Self::L1_2 => "link/l1_2_aspm",
+            Self::L1_1PciPm => "link/l1_1_pcipm",
+            Self::L1_2PciPm => "link/l1_2_pcipm",
+            Self::ClockPm => "link/clkpm",
+        }
+    }
+}
+
+impl Display for PciAspmPolicy {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Default => "default",
+                Self::Performance => "performance",
+                Self::PowerSave => "powersave",
+                Self::PowerSuperSave => "powersupersave",
+            }
+        )
+    }
+}
+
+impl FromStr for PciAspmPolicy {
+    type Err = PciEnumerationError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input.trim() {
+            "default" => Ok(Self::Default),
+            "performance" => Ok(Self::Performance),
+            "powersave" => Ok(Self::PowerSave),
+            "powersupersave" => Ok(Self::PowerSuperSave),
+            other => Err(PciEnumerationError::InvalidAttribute(other.to_owned())),
+        }
+    }
+}
+
+/// Internal function to find the selected policy in the list the kernel
+/// prints, where it is surrounded by brackets.
+fn parse_policy(input: &str) -> Result<PciAspmPolicy, PciEnumerationError> {
+    input
+        .split_whitespace()
+        .find_map(|x| x.strip_prefix('[')?.strip_suffix(']'))
+        .ok_or_else(|| PciEnumerationError::InvalidAttribute(input.to_owned()))?
+        .parse()
+}
+
+/// Gets the global ASPM policy.
+pub fn aspm_policy() -> Result<PciAspmPolicy, PciEnumerationError> {
+    parse_policy(&read_trimmed(Path::new(ASPM_POLICY))?)
+}
+
+/// Sets the global ASPM policy. Requires root, and fails if the firmware
+/// did not grant the OS control over ASPM.
+pub fn set_aspm_policy(policy: PciAspmPolicy) -> Result<(), PciEnumerationError> {
+    write_value(Path::new(ASPM_POLICY), &policy.to_string())
+}
+
+impl PciDeviceAddress {
+    /// Gets whether a link state is enabled. Returns
+    /// [`PciEnumerationError::NotFound`] if the link does not support the
+    /// state, or the kernel is too old to expose it.
+    pub fn aspm_link_state(&self, state: PciAspmLinkState) -> Result<bool, PciEnumerationError> {
+        parse_bool(&read_attribute(self, state.attribute())?)
+    }
+
+    /// Gets every link state the link supports, and whether it is enabled.
+    pub fn aspm_link_states(&self) -> Result<Vec<(PciAspmLinkState, bool)>, PciEnumerationError> {
+        let mut states: Vec<(PciAspmLinkState, bool)> = Vec::new();
+        for state in PciAspmLinkState::ALL {
+            match self.aspm_link_state(state) {
+                Ok(enabled) => states.push((state, enabled)),
+                Err(PciEnumerationError::NotFound) => continue,
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(states)
+    }
+
+    /// Enables or disables a link state. Requires root.
+    pub fn set_aspm_link_state(
+        &self,
+        state: PciAspmLinkState,
+        enabled: bool,
+    ) -> Result<(), PciEnumerationError> {
+        write_attribute(self, state.attribute(), if enabled { "1" } else { "0" })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test finding the selected policy in the kernel's list.
+    #[test]
+    fn test_parse_policy() {
+        assert_eq!(
+            parse_policy("[default] performance powersave powersupersave").unwrap(),
+            PciAspmPolicy::Default
+        );
+        assert_eq!(
+            parse_policy("default performance [powersave] powersupersave").unwrap(),
+            PciAspmPolicy::PowerSave
+        );
+        assert!(parse_policy("default performance").is_err());
+    }
+}
@@ -0,0 +1,104 @@
+// Copyright (c) 2024 Gibson Pilconis, shibedrill, and contributors
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! # About this module
+//! Reads the configuration space of a device through its `config` attribute,
+//! and the MSI-X table of a device by mapping the BAR it lives in through
+//! the matching `resourceN` attribute. Unprivileged users can only read the
+//! first 64 bytes of the configuration space, and mapping a BAR always
+//! requires root.
+
+use crate::config::msi::{PciMsiXEntry, PCI_MSIX_ENTRY_SIZE};
+use crate::config::PciConfigSpace;
+use crate::pci::{PciDeviceAddress, PciEnumerationError};
+
+use std::fs::{read, File};
+use std::io;
+use std::os::unix::io::AsRawFd;
+use std::ptr;
+
+impl PciDeviceAddress {
+    /// Reads the configuration space of the device.
+    pub fn config_space(&self) -> Result<PciConfigSpace, PciEnumerationError> {
+        Ok(PciConfigSpace::from(read(
+            self.sysfs_path().join("config"),
+        )?))
+    }
+
+    /// Reads the entries of the MSI-X table of the device, by mapping the BAR
+    /// that contains it. Returns [`PciEnumerationError::NotFound`] if the
+    /// device does not have an MSI-X capability.
+    pub fn msix_table(&self) -> Result<Vec<PciMsiXEntry>, PciEnumerationError> {
+        let msix = self
+            .config_space()?
+            .msix()
+            .ok_or(PciEnumerationError::NotFound)?;
+
+        let file = File::open(
+            self.sysfs_path()
+                .join(format!("resource{}", msix.table_bir)),
+        )?;
+        let table_length = msix.table_size as usize * PCI_MSIX_ENTRY_SIZE;
+
+        // Mappings have to start on a page boundary, so map from the start of
+        // the page holding the table.
+        let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize;
+        let map_offset = msix.table_offset as usize & !(page_size - 1);
+        let table_start = msix.table_offset as usize - map_offset;
+        let map_length = table_start + table_length;
+
+        let mapping = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                map_length,
+                libc::PROT_READ,
+                libc::MAP_SHARED,
+                file.as_raw_fd(),
+                map_offset as libc::off_t,
+            )
+        };
+        if mapping == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error().into());
+        }
+
+        // The table lives in device memory, which must be read a double word
+        // at a time.
+        let mut table: Vec<u8> = Vec::with_capacity(table_length);
+        for index in 0..table_length / 4 {
+            let dword = unsafe {
+                ptr::read_volatile(
+                    (mapping as *const u8)
+                        .add(table_start + index * 4)
+                        .cast::<u32>(),
+                )
+            };
+            table.extend_from_slice(&dword.to_ne_bytes());
+        }
+
+        unsafe { libc::munmap(mapping, map_length) };
+
+        Ok(PciMsiXEntry::from_table(&table))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    /// Test decoding the interrupt capabilities of every device in the
+    /// system.
+    #[test]
+    fn test_config_space_msi() {
+        println!("Begin test output: test_config_space_msi");
+        let device_list = crate::pci::get_pci_list().unwrap();
+        for device in device_list {
+            let address = device.address.unwrap();
+            let config = address.config_space().unwrap();
+            println!(
+                "{} msi={:?} msix={:?}",
+                address,
+                config.msi(),
+                config.msix()
+            );
+        }
+        println!("End test output: test_config_space_msi");
+    }
+}
@@ -0,0 +1,190 @@
+// Copyright (c) 2024 Gibson Pilconis, shibedrill, and contributors
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! # About this module
+//! Swapping a device at runtime takes a few steps: remove the device from
+//! the kernel, power its slot off, swap it, power the slot back on, and
+//! rescan the bus so the kernel finds the new device. This module wraps each
+//! of those steps, and maps the physical slot names printed on the chassis to
+//! the devices sitting in them. Everything except listing slots requires
+//! root:
+//! ```rust,no_run
+//! # use libpci_rs::linux::hotplug::{pci_slots, rescan_bus};
+//! for slot in pci_slots().unwrap() {
+//!     if slot.name == "4" {
+//!         for address in slot.devices().unwrap() {
+//!             address.remove().unwrap();
+//!         }
+//!         slot.set_power(false).unwrap();
+//!         // Swap the drive here.
+//!         slot.set_power(true).unwrap();
+//!     }
+//! }
+//! rescan_bus().unwrap();
+//! ```
+
+use crate::linux::{parse_bool, read_trimmed, write_attribute, write_value, SYSFS_PCI_DEVICES};
+use crate::pci::{PciDeviceAddress, PciEnumerationError};
+
+use std::fs::read_dir;
+use std::path::{Path, PathBuf};
+
+/// The file that rescans every PCI bus when written to.
+const SYSFS_PCI_RESCAN: &str = "/sys/bus/pci/rescan";
+/// The directory sysfs exposes every physical slot under.
+const SYSFS_PCI_SLOTS: &str = "/sys/bus/pci/slots";
+
+/// A physical slot that devices can be plugged into.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PciSlot {
+    /// The name of the slot, usually the number printed on the chassis.
+    pub name: String,
+    /// The domain the slot is on.
+    pub domain: u32,
+    /// The bus the slot is on.
+    pub bus: u8,
+    /// The device number of the slot on its bus. Some firmware does not
+    /// report it, in which case every device on the bus is in the slot.
+    pub device: Option<u8>,
+}
+
+/// Internal function to parse the address of a slot, which is either
+/// `domain:bus:device` or just `domain:bus`.
+fn parse_slot_address(input: &str) -> Result<(u32, u8, Option<u8>), PciEnumerationError> {
+    let parts: Vec<&str> = input.trim().split(':').collect();
+    match parts.as_slice() {
+        [domain, bus] => Ok((
+            u32::from_str_radix(domain, 16)?,
+            u8::from_str_radix(bus, 16)?,
+            None,
+        )),
+        [domain, bus, device] => Ok((
+            u32::from_str_radix(domain, 16)?,
+            u8::from_str_radix(bus, 16)?,
+            Some(u8::from_str_radix(device, 16)?),
+        )),
+        _ => Err(PciEnumerationError::InvalidAttribute(input.to_owned())),
+    }
+}
+
+/// Rescans every PCI bus for devices that were added or powered on.
+/// Requires root.
+pub fn rescan_bus() -> Result<(), PciEnumerationError> {
+    write_value(Path::new(SYSFS_PCI_RESCAN), "1")
+}
+
+/// Gets every physical slot the platform reports, sorted by name. Most
+/// desktops report none.
+pub fn pci_slots() -> Result<Vec<PciSlot>, PciEnumerationError> {
+    let mut slots: Vec<PciSlot> = Vec::new();
+
+    let directory = match read_dir(SYSFS_PCI_SLOTS) {
+        Ok(directory) => directory,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(slots),
+        Err(err) => return Err(err.into()),
+    };
+
+    for entry in directory {
+        let entry = entry?;
+        let (domain, bus, device) =
+            parse_slot_address(&read_trimmed(&entry.path().join("address"))?)?;
+        slots.push(PciSlot {
+            name: entry.file_name().to_string_lossy().into_owned(),
+            domain,
+            bus,
+            device,
+        });
+    }
+
+    slots.sort();
+    Ok(slots)
+}
+
+impl PciSlot {
+    /// Returns the sysfs directory of the slot, i.e.
+    /// `/sys/bus/pci/slots/<name>`.
+    pub fn sysfs_path(&self) -> PathBuf {
+        Path::new(SYSFS_PCI_SLOTS).join(&self.name)
+    }
+
+    /// Returns true if a device at the given address is in the slot.
+    pub fn contains(&self, address: &PciDeviceAddress) -> bool {
+        self.domain == address.domain
+            && self.bus == address.bus
+            && self.device.map_or(true, |x| x == address.device)
+    }
+
+    /// Gets the addresses of every device function in the slot, sorted.
+    pub fn devices(&self) -> Result<Vec<PciDeviceAddress>, PciEnumerationError> {
+        let mut devices: Vec<PciDeviceAddress> = Vec::new();
+        for entry in read_dir(SYSFS_PCI_DEVICES)? {
+            let address =
+                PciDeviceAddress::try_from(entry?.file_name().to_string_lossy().into_owned())?;
+            if self.contains(&address) {
+                devices.push(address);
+            }
+        }
+        devices.sort();
+        Ok(devices)
+    }
+
+    /// Gets whether the slot is powered on. Only slots managed by a hotplug
+    /// driver can report this.
+    pub fn power(&self) -> Result<bool, PciEnumerationError> {
+        parse_bool(&read_trimmed(&self.sysfs_path().join("power"))?)
+    }
+
+    /// Powers the slot on or off. Remove the devices in the slot before
+    /// powering it off. Requires root.
+    pub fn set_power(&self, on: bool) -> Result<(), PciEnumerationError> {
+        write_value(&self.sysfs_path().join("power"), if on { "1" } else { "0" })
+    }
+}
+
+impl PciDeviceAddress {
+    /// Gets the physical slot the device is in, if the platform reports one.
+    pub fn slot(&self) -> Result<Option<PciSlot>, PciEnumerationError> {
+        Ok(pci_slots()?.into_iter().find(|x| x.contains(self)))
+    }
+
+    /// Removes the device from the kernel, unbinding its driver. The device
+    /// reappears after the next rescan. Requires root.
+    pub fn remove(&self) -> Result<(), PciEnumerationError> {
+        write_attribute(self, "remove", "1")
+    }
+
+    /// Rescans the bus the device is on. If the device is a bridge, this also
+    /// rescans every bus below it. Requires root.
+    pub fn rescan(&self) -> Result<(), PciEnumerationError> {
+        write_attribute(self, "rescan", "1")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test parsing slot addresses and matching devices against them.
+    #[test]
+    fn test_slot_address() {
+        let (domain, bus, device) = parse_slot_address("0000:3b:00\n").unwrap();
+        let slot = PciSlot {
+            name: "4".to_owned(),
+            domain,
+            bus,
+            device,
+        };
+        let mut address = PciDeviceAddress {
+            domain: 0,
+            bus: 0x3b,
+            device: 0,
+            function: 1,
+        };
+        assert!(slot.contains(&address));
+        address.device = 1;
+        assert!(!slot.contains(&address));
+
+        assert_eq!(parse_slot_address("0001:05").unwrap(), (1, 5, None));
+        assert!(parse_slot_address("0000").is_err());
+    }
+}
@@ -0,0 +1,67 @@
+// Copyright (c) 2024 Gibson Pilconis, shibedrill, and contributors
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! # About this module
+//! PCI Express devices negotiate the speed and width of their link when it
+//! trains, and can end up below what both ends support when a slot, riser,
+//! or cable is marginal. This module reports the negotiated and maximum
+//! values the kernel exposes:
+//! ```rust
+//! # use libpci_rs::pci::get_pci_list;
+//! for device in get_pci_list().unwrap() {
+//!     let Some(address) = device.address else { continue };
+//!     println!(
+//!         "{}: {:?} x{:?} (max {:?} x{:?})",
+//!         address,
+//!         address.current_link_speed(),
+//!         address.current_link_width(),
+//!         address.max_link_speed(),
+//!         address.max_link_width()
+//!     );
+//! }
+//! ```
+//! Conventional PCI devices have no link, so every function here returns
+//! [`None`] for them.
+
+use crate::linux::{parse_decimal, read_attribute};
+use crate::pci::{PciDeviceAddress, PciEnumerationError};
+
+/// Internal function to read an attribute that only exists for PCI Express
+/// devices.
+fn read_link_attribute(
+    address: &PciDeviceAddress,
+    attribute: &str,
+) -> Result<Option<String>, PciEnumerationError> {
+    match read_attribute(address, attribute) {
+        Ok(value) => Ok(Some(value)),
+        Err(PciEnumerationError::NotFound) => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+impl PciDeviceAddress {
+    /// Gets the negotiated speed of the link, as the kernel prints it (e.g.
+    /// `16.0 GT/s PCIe`).
+    pub fn current_link_speed(&self) -> Result<Option<String>, PciEnumerationError> {
+        read_link_attribute(self, "current_link_speed")
+    }
+
+    /// Gets the negotiated width of the link, in lanes.
+    pub fn current_link_width(&self) -> Result<Option<u8>, PciEnumerationError> {
+        read_link_attribute(self, "current_link_width")?
+            .map(|x| parse_decimal(&x))
+            .transpose()
+    }
+
+    /// Gets the maximum speed the device supports, as the kernel prints it.
+    pub fn max_link_speed(&self) -> Result<Option<String>, PciEnumerationError> {
+        read_link_attribute(self, "max_link_speed")
+    }
+
+    /// Gets the maximum width the device supports, in lanes.
+    pub fn max_link_width(&self) -> Result<Option<u8>, PciEnumerationError> {
+        read_link_attribute(self, "max_link_width")?
+            .map(|x| parse_decimal(&x))
+            .transpose()
+    }
+}
@@ -0,0 +1,203 @@
+// Copyright (c) 2024 Gibson Pilconis, shibedrill, and contributors
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! # About this module
+//! This module contains functionality that only exists on Linux (and
+//! Android), where the kernel exposes a lot more information about a PCI
+//! device than its IDs through sysfs and procfs. Everything here works on a
+//! [PciDeviceAddress](crate::pci::PciDeviceAddress), so you can pair it with
+//! the devices returned by [get_pci_list](crate::pci::get_pci_list):
+//! ```rust
+//! # use libpci_rs::pci::get_pci_list;
+//! for device in get_pci_list().unwrap() {
+//!     if let Some(address) = device.address {
+//!         println!("{} is on NUMA node {:?}", address, address.numa_node());
+//!     }
+//! }
+//! ```
+//! Most attributes are readable by any user, but anything that changes the
+//! state of a device requires root.
+
+use crate::pci::{PciDeviceAddress, PciEnumerationError};
+
+use std::fs::{read_link, read_to_string, write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Device affinity information: NUMA node, local CPUs, and interrupts.
+pub mod affinity;
+/// ASPM policy and per-link power state control.
+pub mod aspm;
+/// Configuration space and MSI-X table access.
+pub mod config;
+/// Bus rescans, device removal, and hotplug slot control.
+pub mod hotplug;
+/// PCI Express link speed and width.
+pub mod link;
+/// Finding the kernel modules able to drive a device.
+pub mod modules;
+/// Device power states and runtime power management.
+pub mod power;
+/// Device reset methods and triggering resets.
+pub mod reset;
+/// Watching for devices being added, removed, or changed.
+pub mod uevent;
+
+/// The directory sysfs exposes every PCI device under.
+pub(crate) const SYSFS_PCI_DEVICES: &str = "/sys/bus/pci/devices";
+
+impl PciDeviceAddress {
+    /// Returns the sysfs directory of the device at this address, i.e.
+    /// `/sys/bus/pci/devices/0000:00:00.0`.
+    pub fn sysfs_path(&self) -> PathBuf {
+        Path::new(SYSFS_PCI_DEVICES).join(self.to_string())
+    }
+
+    /// Gets the name of the driver bound to the device. Returns [`None`] if
+    /// no driver is bound.
+    pub fn driver(&self) -> Result<Option<String>, PciEnumerationError> {
+        match read_link(self.sysfs_path().join("driver")) {
+            Ok(target) => Ok(target.file_name().map(|x| x.to_string_lossy().into_owned())),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Gets the IOMMU group the device belongs to. Returns [`None`] if the
+    /// IOMMU is disabled or the device isn't in a group.
+    pub fn iommu_group(&self) -> Result<Option<u32>, PciEnumerationError> {
+        match read_link(self.sysfs_path().join("iommu_group")) {
+            Ok(target) => match target.file_name() {
+                Some(group) => Ok(Some(parse_decimal(&group.to_string_lossy())?)),
+                None => Ok(None),
+            },
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+/// Internal function to read a file in sysfs or procfs with the trailing
+/// newline removed.
+pub(crate) fn read_trimmed(path: &Path) -> Result<String, PciEnumerationError> {
+    Ok(read_to_string(path)?.trim().to_owned())
+}
+
+/// Internal function to write a value to a file in sysfs or procfs.
+pub(crate) fn write_value(path: &Path, value: &str) -> Result<(), PciEnumerationError> {
+    Ok(write(path, value)?)
+}
+
+/// Internal function to read a device attribute from its sysfs directory.
+pub(crate) fn read_attribute(
+    address: &PciDeviceAddress,
+    attribute: &str,
+) -> Result<String, PciEnumerationError> {
+    read_trimmed(&address.sysfs_path().join(attribute))
+}
+
+/// Internal function to write a device attribute in its sysfs directory.
+pub(crate) fn write_attribute(
+    address: &PciDeviceAddress,
+    attribute: &str,
+    value: &str,
+) -> Result<(), PciEnumerationError> {
+    write_value(&address.sysfs_path().join(attribute), value)
+}
+
+/// Parses a decimal value out of a sysfs attribute. Unlike the IDs, which are
+/// hex, things like IRQ numbers and NUMA nodes are written in decimal.
+pub(crate) fn parse_decimal<T: FromStr<Err = std::num::ParseIntError>>(
+    input: &str,
+) -> Result<T, PciEnumerationError> {
+    Ok(input.trim().parse::<T>()?)
+}
+
+/// Parses a boolean sysfs attribute, which the kernel writes as `0` or `1`.
+pub(crate) fn parse_bool(input: &str) -> Result<bool, PciEnumerationError> {
+    match input.trim() {
+        "0" => Ok(false),
+        "1" => Ok(true),
+        other => Err(PciEnumerationError::InvalidAttribute(other.to_owned())),
+    }
+}
+
+/// Parses a kernel CPU list (e.g. `0-3,8,10-11`) into a sorted list of
+/// CPU numbers.
+pub(crate) fn parse_cpulist(input: &str) -> Result<Vec<u32>, PciEnumerationError> {
+    let mut cpus: Vec<u32> = Vec::new();
+
+    for range in input.trim().split(',').filter(|x| !x.is_empty()) {
+        if let Some((start, end)) = range.split_once('-') {
+            let start: u32 = parse_decimal(start)?;
+            let end: u32 = parse_decimal(end)?;
+            cpus.extend(start..=end);
+        } else {
+            cpus.push(parse_decimal(range)?);
+        }
+    }
+
+    cpus.sort_unstable();
+    cpus.dedup();
+    Ok(cpus)
+}
+
+/// Parses a kernel CPU mask (e.g. `ffffffff,00000001`) into a sorted list of
+/// CPU numbers. The mask is made of comma-separated 32-bit words, with the
+/// most significant word first.
+pub(crate) fn parse_cpumask(input: &str) -> Result<Vec<u32>, PciEnumerationError> {
+    let mut cpus: Vec<u32> = Vec::new();
+
+    for (word_index, word) in input.trim().rsplit(',').enumerate() {
+        let word = u32::from_str_radix(word, 16)?;
+        for bit in 0..32 {
+            if word & (1 << bit) != 0 {
+                cpus.push(word_index as u32 * 32 + bit);
+            }
+        }
+    }
+
+    cpus.sort_unstable();
+    Ok(cpus)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test parsing the kernel's CPU list format.
+    #[test]
+    fn test_parse_cpulist() {
+        assert_eq!(
+            parse_cpulist("0-3,8,10-11\n").unwrap(),
+            vec![0, 1, 2, 3, 8, 10, 11]
+        );
+        assert_eq!(parse_cpulist("").unwrap(), Vec::<u32>::new());
+        assert!(parse_cpulist("0-x").is_err());
+    }
+
+    /// Test parsing the kernel's CPU mask format.
+    #[test]
+    fn test_parse_cpumask() {
+        assert_eq!(
+            parse_cpumask("00000001,0000000f\n").unwrap(),
+            vec![0, 1, 2, 3, 32]
+        );
+        assert_eq!(parse_cpumask("0").unwrap(), Vec::<u32>::new());
+    }
+
+    /// Test the sysfs path of a device address.
+    #[test]
+    fn test_sysfs_path() {
+        let address = PciDeviceAddress {
+            domain: 0,
+            bus: 0x3b,
+            device: 0,
+            function: 1,
+        };
+        assert_eq!(
+            address.sysfs_path(),
+            PathBuf::from("/sys/bus/pci/devices/0000:3b:00.1")
+        );
+    }
+}
@@ -0,0 +1,177 @@
+// Copyright (c) 2024 Gibson Pilconis, shibedrill, and contributors
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! # About this module
+//! Every device has a modalias, a string made from its IDs that kernel
+//! modules match against with wildcard patterns listed in `modules.alias`.
+//! This module finds the modules of the running kernel whose patterns match,
+//! which are the modules that could drive the device, whether or not one of
+//! them is loaded:
+//! ```rust
+//! # use libpci_rs::pci::get_pci_list;
+//! for device in get_pci_list().unwrap() {
+//!     let Some(address) = device.address else { continue };
+//!     println!("{}: {:?}", address, address.kernel_modules());
+//! }
+//! ```
+
+use crate::linux::{read_attribute, read_trimmed};
+use crate::pci::{PciDeviceAddress, PciEnumerationError};
+
+use std::fs::read_to_string;
+use std::path::{Path, PathBuf};
+
+/// Internal function to get the path of the `modules.alias` file of the
+/// running kernel.
+fn modules_alias_path() -> Result<PathBuf, PciEnumerationError> {
+    let release = read_trimmed(Path::new("/proc/sys/kernel/osrelease"))?;
+    Ok(Path::new("/lib/modules")
+        .join(release)
+        .join("modules.alias"))
+}
+
+impl PciDeviceAddress {
+    /// Gets the modalias of the device, like
+    /// `pci:v00008086d0000A0F0sv00008086sd00000070bc02sc80i00`.
+    pub fn modalias(&self) -> Result<String, PciEnumerationError> {
+        read_attribute(self, "modalias")
+    }
+
+    /// Gets the kernel modules of the running kernel able to drive the
+    /// device, in the order `modules.alias` lists them.
+    pub fn kernel_modules(&self) -> Result<Vec<String>, PciEnumerationError> {
+        let aliases = read_to_string(modules_alias_path()?)?;
+        Ok(matching_modules(&aliases, &self.modalias()?))
+    }
+}
+
+/// Internal function to find the modules in a `modules.alias` file with a PCI
+/// alias matching a modalias. Each module is listed once.
+pub(crate) fn matching_modules(aliases: &str, modalias: &str) -> Vec<String> {
+    let mut modules: Vec<String> = Vec::new();
+
+    for line in aliases.lines() {
+        let mut fields = line.split_whitespace();
+        let (Some("alias"), Some(pattern), Some(module)) =
+            (fields.next(), fields.next(), fields.next())
+        else {
+            continue;
+        };
+        if pattern.starts_with("pci:")
+            && glob_match(pattern.as_bytes(), modalias.as_bytes())
+            && !modules.iter().any(|x| x == module)
+        {
+            modules.push(module.to_owned());
+        }
+    }
+
+    modules
+}
+
+/// Internal function to match a wildcard pattern the way `fnmatch` does,
+/// supporting `*`, `?` and bracket expressions like `[0-9]`.
+fn glob_match(pattern: &[u8], input: &[u8]) -> bool {
+    // Where to resume if the last `*` has to match more of the input.
+    let mut backtrack: Option<(usize, usize)> = None;
+    let (mut p, mut i) = (0, 0);
+
+    while i < input.len() {
+        let step = match pattern.get(p) {
+            Some(b'*') => {
+                backtrack = Some((p + 1, i));
+                p += 1;
+                continue;
+            }
+            Some(b'?') => Some(1),
+            Some(b'[') => bracket_match(&pattern[p..], input[i]),
+            Some(&x) if x == input[i] => Some(1),
+            _ => None,
+        };
+        match (step, backtrack) {
+            (Some(length), _) => {
+                p += length;
+                i += 1;
+            }
+            (None, Some((star_p, star_i))) => {
+                backtrack = Some((star_p, star_i + 1));
+                p = star_p;
+                i = star_i + 1;
+            }
+            (None, None) => return false,
+        }
+    }
+
+    pattern[p..].iter().all(|x| *x == b'*')
+}
+
+/// Internal function to match a byte against the bracket expression at the
+/// start of `pattern`. Returns the length of the expression if it matches.
+fn bracket_match(pattern: &[u8], byte: u8) -> Option<usize> {
+    let negated = matches!(pattern.get(1), Some(b'!' | b'^'));
+    let start = if negated { 2 } else { 1 };
+    // A `]` right after the opening bracket is part of the set.
+    let end = start + 1 + pattern.get(start + 1..)?.iter().position(|x| *x == b']')?;
+
+    let set = &pattern[start..end];
+    let mut matched = false;
+    let mut index = 0;
+    while index < set.len() {
+        if index + 2 < set.len() && set[index + 1] == b'-' {
+            matched |= (set[index]..=set[index + 2]).contains(&byte);
+            index += 3;
+        } else {
+            matched |= set[index] == byte;
+            index += 1;
+        }
+    }
+
+    (matched != negated).then_some(end + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test matching wildcard patterns.
+    #[test]
+    fn test_glob_match() {
+        let matches = |pattern: &str, input: &str| glob_match(pattern.as_bytes(), input.as_bytes());
+        assert!(matches(
+            "pci:v00008086d*sv*sd*bc*sc*i*",
+            "pci:v00008086d0000A0F0sv00008086sd00000070bc02sc80i00"
+        ));
+        assert!(!matches(
+            "pci:v00008086d*sv*sd*bc*sc*i*",
+            "pci:v00001AF4d00001042sv00001AF4sd00000002bc01sc80i00"
+        ));
+        assert!(matches("a?c", "abc"));
+        assert!(!matches("a?c", "ac"));
+        assert!(matches("*", ""));
+        assert!(matches("a*b*c", "aXbYbZc"));
+        assert!(matches("bc0[0-2]", "bc01"));
+        assert!(!matches("bc0[!0-2]", "bc01"));
+        assert!(matches("[]]", "]"));
+        assert!(!matches("[abc", "a"));
+    }
+
+    /// Test finding the modules matching a modalias.
+    #[test]
+    fn test_matching_modules() {
+        let aliases = "# Aliases extracted from modules themselves.\n\
+                       alias pci:v00008086d0000A0F0sv*sd*bc*sc*i* iwlwifi\n\
+                       alias pci:v*d*sv*sd*bc02sc80i* iwlwifi\n\
+                       alias usb:v8086p*d*dc*dsc*dp*ic*isc*ip*in* not_pci\n\
+                       alias pci:v00008086d*sv*sd*bc02sc80i* intel_wireless\n\
+                       alias pci:v00001AF4d*sv*sd*bc*sc*i* virtio_pci\n\
+                       alias malformed\n";
+        let modalias = "pci:v00008086d0000A0F0sv00008086sd00000070bc02sc80i00";
+        assert_eq!(
+            matching_modules(aliases, modalias),
+            vec!["iwlwifi", "intel_wireless"]
+        );
+        assert!(
+            matching_modules(aliases, "usb:v8086p0001d0000dc00dsc00dp00ic00isc00ip00in00")
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,173 @@
+// Copyright (c) 2024 Gibson Pilconis, shibedrill, and contributors
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! # About this module
+//! The kernel suspends idle devices on its own through runtime power
+//! management, and can put them into D3cold (removing main power) if the
+//! platform allows it. This module shows where a device is in that process
+//! and lets you change the policy. Reading works as any user, while the
+//! setters require root:
+//! ```rust
+//! # use libpci_rs::pci::get_pci_list;
+//! for device in get_pci_list().unwrap() {
+//!     let Some(address) = device.address else { continue };
+//!     println!(
+//!         "{}: {:?} {:?} d3cold_allowed={:?}",
+//!         address,
+//!         address.power_state(),
+//!         address.runtime_status(),
+//!         address.d3cold_allowed()
+//!     );
+//! }
+//! ```
+
+use crate::config::power::PciPowerState;
+use crate::linux::{parse_bool, read_attribute, write_attribute};
+use crate::pci::{PciDeviceAddress, PciEnumerationError};
+
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+/// The runtime power management status of a device, from
+/// `power/runtime_status`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PciRuntimeStatus {
+    /// The device is in use.
+    Active,
+    /// The device is being suspended.
+    Suspending,
+    /// The device is suspended.
+    Suspended,
+    /// The device is being resumed.
+    Resuming,
+    /// Runtime power management failed for the device.
+    Error,
+    /// Runtime power management is disabled for the device.
+    Unsupported,
+}
+
+/// The runtime power management policy of a device, from `power/control`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PciRuntimePmControl {
+    /// The kernel may suspend the device while it is idle.
+    Auto,
+    /// The device is kept on at all times.
+    On,
+}
+
+impl FromStr for PciRuntimeStatus {
+    type Err = PciEnumerationError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input.trim() {
+            "active" => Ok(Self::Active),
+            "suspending" => Ok(Self::Suspending),
+            "suspended" => Ok(Self::Suspended),
+            "resuming" => Ok(Self::Resuming),
+            "error" => Ok(Self::Error),
+            "unsupported" => Ok(Self::Unsupported),
+            other => Err(PciEnumerationError::InvalidAttribute(other.to_owned())),
+        }
+    }
+}
+
+impl Display for PciRuntimePmControl {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Auto => "auto",
+                Self::On => "on",
+            }
+        )
+    }
+}
+
+impl FromStr for PciRuntimePmControl {
+    type Err = PciEnumerationError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input.trim() {
+            "auto" => Ok(Self::Auto),
+            "on" => Ok(Self::On),
+            other => Err(PciEnumerationError::InvalidAttribute(other.to_owned())),
+        }
+    }
+}
+
+impl PciDeviceAddress {
+    /// Gets the power state the kernel believes the device is in. Unlike the
+    /// Power Management capability, this can also report D3cold.
+    pub fn power_state(&self) -> Result<PciPowerState, PciEnumerationError> {
+        read_attribute(self, "power_state")?.parse()
+    }
+
+    /// Gets the runtime power management status of the device.
+    pub fn runtime_status(&self) -> Result<PciRuntimeStatus, PciEnumerationError> {
+        read_attribute(self, "power/runtime_status")?.parse()
+    }
+
+    /// Gets the runtime power management policy of the device.
+    pub fn runtime_pm_control(&self) -> Result<PciRuntimePmControl, PciEnumerationError> {
+        read_attribute(self, "power/control")?.parse()
+    }
+
+    /// Sets the runtime power management policy of the device. Requires
+    /// root.
+    pub fn set_runtime_pm_control(
+        &self,
+        control: PciRuntimePmControl,
+    ) -> Result<(), PciEnumerationError> {
+        write_attribute(self, "power/control", &control.to_string())
+    }
+
+    /// Gets whether the device is allowed to enter D3cold.
+    pub fn d3cold_allowed(&self) -> Result<bool, PciEnumerationError> {
+        parse_bool(&read_attribute(self, "d3cold_allowed")?)
+    }
+
+    /// Sets whether the device is allowed to enter D3cold. Requires root.
+    pub fn set_d3cold_allowed(&self, allowed: bool) -> Result<(), PciEnumerationError> {
+        write_attribute(self, "d3cold_allowed", if allowed { "1" } else { "0" })
+    }
+
+    /// Gets whether the device is allowed to wake the system up. Returns
+    /// [`None`] if the device can't wake the system up at all.
+    pub fn wakeup(&self) -> Result<Option<bool>, PciEnumerationError> {
+        match read_attribute(self, "power/wakeup") {
+            Ok(wakeup) => match wakeup.as_str() {
+                "enabled" => Ok(Some(true)),
+                "disabled" => Ok(Some(false)),
+                "" => Ok(None),
+                other => Err(PciEnumerationError::InvalidAttribute(other.to_owned())),
+            },
+            Err(PciEnumerationError::NotFound) => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    /// Test reading the power management information of every device in the
+    /// system.
+    #[test]
+    fn test_device_power() {
+        println!("Begin test output: test_device_power");
+        let device_list = crate::pci::get_pci_list().unwrap();
+        for device in device_list {
+            let address = device.address.unwrap();
+            println!(
+                "{} state={:?} runtime={:?} control={:?} d3cold={:?} wakeup={:?}",
+                address,
+                address.power_state(),
+                address.runtime_status(),
+                address.runtime_pm_control(),
+                address.d3cold_allowed(),
+                address.wakeup().unwrap()
+            );
+        }
+        println!("End test output: test_device_power");
+    }
+}
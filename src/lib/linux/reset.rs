@@ -0,0 +1,167 @@
+// Copyright (c) 2024 Gibson Pilconis, shibedrill, and contributors
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! # About this module
+//! The kernel knows several ways of resetting a device, and tries them in
+//! order until one works. This module lists the methods available for a
+//! device, lets you choose which ones are tried and in what order, and
+//! triggers a reset. Everything except listing the methods requires root:
+//! ```rust,no_run
+//! # use libpci_rs::linux::reset::PciResetMethod;
+//! # use libpci_rs::pci::PciDeviceAddress;
+//! let address = PciDeviceAddress::try_from("0000:3b:00.1".to_string()).unwrap();
+//! if address.reset_methods().unwrap().contains(&PciResetMethod::Flr) {
+//!     address.set_reset_methods(&[PciResetMethod::Flr]).unwrap();
+//!     address.reset().unwrap();
+//! }
+//! ```
+
+use crate::linux::{read_attribute, write_attribute};
+use crate::pci::{PciDeviceAddress, PciEnumerationError};
+
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+/// A way the kernel can reset a device.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PciResetMethod {
+    /// A quirk specific to the device.
+    DeviceSpecific,
+    /// The `_RST` method of the ACPI firmware.
+    Acpi,
+    /// A PCI Express Function Level Reset.
+    Flr,
+    /// A Function Level Reset through the Advanced Features capability.
+    AfFlr,
+    /// A D3hot to D0 power state transition.
+    Pm,
+    /// A secondary bus reset of the bridge above the device. This resets
+    /// every device on the bus.
+    Bus,
+    /// A secondary bus reset through the CXL port above the device.
+    CxlBus,
+    /// A method this version of libpci-rs does not know about.
+    Other(String),
+}
+
+impl Display for PciResetMethod {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::DeviceSpecific => "device_specific",
+                Self::Acpi => "acpi",
+                Self::Flr => "flr",
+                Self::AfFlr => "af_flr",
+                Self::Pm => "pm",
+                Self::Bus => "bus",
+                Self::CxlBus => "cxl_bus",
+                Self::Other(name) => name,
+            }
+        )
+    }
+}
+
+impl FromStr for PciResetMethod {
+    type Err = PciEnumerationError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input.trim() {
+            "" => Err(PciEnumerationError::InvalidAttribute(input.to_owned())),
+            "device_specific" => Ok(Self::DeviceSpecific),
+            "acpi" => Ok(Self::Acpi),
+            "flr" => Ok(Self::Flr),
+            "af_flr" => Ok(Self::AfFlr),
+            "pm" => Ok(Self::Pm),
+            "bus" => Ok(Self::Bus),
+            "cxl_bus" => Ok(Self::CxlBus),
+            other => Ok(Self::Other(other.to_owned())),
+        }
+    }
+}
+
+impl PciDeviceAddress {
+    /// Gets the reset methods the kernel will try for the device, in order.
+    /// The list is empty if the device can't be reset. Requires Linux 5.15
+    /// or later.
+    pub fn reset_methods(&self) -> Result<Vec<PciResetMethod>, PciEnumerationError> {
+        match read_attribute(self, "reset_method") {
+            Ok(methods) => methods.split_whitespace().map(str::parse).collect(),
+            Err(PciEnumerationError::NotFound) => Ok(vec![]),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Sets which reset methods the kernel will try for the device, and in
+    /// what order. Every method has to be available for the device. Passing
+    /// an empty list disables resetting the device. Requires root.
+    pub fn set_reset_methods(&self, methods: &[PciResetMethod]) -> Result<(), PciEnumerationError> {
+        write_attribute(self, "reset_method", &format_reset_methods(methods))
+    }
+
+    /// Restores the reset methods the kernel tries for the device to the
+    /// default list and order. Requires root.
+    pub fn set_default_reset_methods(&self) -> Result<(), PciEnumerationError> {
+        write_attribute(self, "reset_method", "default")
+    }
+
+    /// Resets the device using the first reset method that works. Requires
+    /// root.
+    pub fn reset(&self) -> Result<(), PciEnumerationError> {
+        write_attribute(self, "reset", "1")
+    }
+}
+
+/// Internal function to format reset methods the way the reset_method
+/// attribute takes them. The kernel ignores empty writes, so an empty list
+/// is written as a blank line, which it reads as disabling resets.
+fn format_reset_methods(methods: &[PciResetMethod]) -> String {
+    if methods.is_empty() {
+        return "\n".to_owned();
+    }
+    let methods: Vec<String> = methods.iter().map(|x| x.to_string()).collect();
+    methods.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test converting reset methods to and from their kernel names.
+    #[test]
+    fn test_reset_method_names() {
+        let methods: Vec<PciResetMethod> = "flr af_flr pm bus cxl_bus quirky"
+            .split_whitespace()
+            .map(|x| x.parse().unwrap())
+            .collect();
+        assert_eq!(
+            methods,
+            vec![
+                PciResetMethod::Flr,
+                PciResetMethod::AfFlr,
+                PciResetMethod::Pm,
+                PciResetMethod::Bus,
+                PciResetMethod::CxlBus,
+                PciResetMethod::Other("quirky".to_owned()),
+            ]
+        );
+        for method in methods {
+            assert_eq!(
+                method.to_string().parse::<PciResetMethod>().unwrap(),
+                method
+            );
+        }
+    }
+
+    /// Test formatting reset methods for the reset_method attribute.
+    #[test]
+    fn test_format_reset_methods() {
+        assert_eq!(
+            format_reset_methods(&[PciResetMethod::Flr, PciResetMethod::Bus]),
+            "flr bus"
+        );
+        assert_eq!(format_reset_methods(&[PciResetMethod::Pm]), "pm");
+        assert_eq!(format_reset_methods(&[]), "\n");
+    }
+}
@@ -0,0 +1,338 @@
+// Copyright (c) 2024 Gibson Pilconis, shibedrill, and contributors
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! # About this module
+//! Instead of polling [get_pci_list](crate::pci::get_pci_list) and comparing
+//! the results, you can have the kernel tell you whenever a device is added,
+//! removed, or bound to a driver. The kernel broadcasts these as uevents on
+//! a netlink socket, which a [`PciWatcher`] listens on:
+//! ```rust,no_run
+//! # use libpci_rs::linux::uevent::{PciEvent, PciWatcher};
+//! for event in PciWatcher::new().unwrap() {
+//!     match event.unwrap() {
+//!         PciEvent::Added(device) => println!("{} added", device.address),
+//!         PciEvent::Removed(device) => println!("{} removed", device.address),
+//!         _ => {}
+//!     }
+//! }
+//! ```
+//! Listening does not require root. A watcher can also read uevents from any
+//! [`BufRead`], one `KEY=VALUE` pair per line with a blank line after each
+//! event, which is handy for tests and replaying recorded events.
+
+use crate::pci::{PciDeviceAddress, PciEnumerationError};
+
+use std::io::{self, BufRead};
+use std::mem;
+use std::os::unix::io::{AsRawFd, FromRawFd, OwnedFd};
+
+/// The netlink multicast group the kernel sends uevents to.
+const UEVENT_KERNEL_GROUP: u32 = 1;
+/// The largest uevent the kernel sends, with room to spare.
+const UEVENT_BUFFER_SIZE: usize = 8192;
+
+/// The device a uevent is about.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct PciUevent {
+    /// The address of the device, from `PCI_SLOT_NAME`.
+    pub address: PciDeviceAddress,
+    /// The vendor ID of the device, from `PCI_ID`.
+    pub vendor_id: Option<u16>,
+    /// The device ID of the device, from `PCI_ID`.
+    pub device_id: Option<u16>,
+    /// The class code of the device, from `PCI_CLASS`. This holds the class,
+    /// subclass, and programming interface.
+    pub class_code: Option<u32>,
+    /// The driver the device is bound to, from `DRIVER`.
+    pub driver: Option<String>,
+}
+
+/// Something that happened to a PCI device.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PciEvent {
+    /// The device was added.
+    Added(PciUevent),
+    /// The device was removed.
+    Removed(PciUevent),
+    /// The device was bound to a driver.
+    Bound(PciUevent),
+    /// The device was unbound from its driver.
+    Unbound(PciUevent),
+    /// Something about the device changed.
+    Changed(PciUevent),
+}
+
+impl PciEvent {
+    /// Returns the device the event is about.
+    pub fn device(&self) -> &PciUevent {
+        match self {
+            Self::Added(device)
+            | Self::Removed(device)
+            | Self::Bound(device)
+            | Self::Unbound(device)
+            | Self::Changed(device) => device,
+        }
+    }
+
+    /// Returns the address of the device the event is about.
+    pub fn address(&self) -> PciDeviceAddress {
+        self.device().address
+    }
+
+    /// Parses an event from the `KEY=VALUE` fields of a uevent. Fields
+    /// without an `=` are ignored. Returns [`None`] if the uevent is not
+    /// about a PCI device, or is an action libpci-rs does not report.
+    pub fn from_fields<'a, I>(fields: I) -> Option<PciEvent>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        let mut action: Option<&str> = None;
+        let mut subsystem: Option<&str> = None;
+        let mut address: Option<PciDeviceAddress> = None;
+        let mut ids: Option<(u16, u16)> = None;
+        let mut class_code: Option<u32> = None;
+        let mut driver: Option<String> = None;
+
+        for (key, value) in fields.into_iter().filter_map(|x| x.split_once('=')) {
+            match key {
+                "ACTION" => action = Some(value),
+                "SUBSYSTEM" => subsystem = Some(value),
+                "PCI_SLOT_NAME" => address = PciDeviceAddress::try_from(value.to_owned()).ok(),
+                "PCI_ID" => {
+                    ids = value.split_once(':').and_then(|(vendor, device)| {
+                        Some((
+                            u16::from_str_radix(vendor, 16).ok()?,
+                            u16::from_str_radix(device, 16).ok()?,
+                        ))
+                    })
+                }
+                "PCI_CLASS" => class_code = u32::from_str_radix(value, 16).ok(),
+                "DRIVER" => driver = Some(value.to_owned()),
+                _ => {}
+            }
+        }
+
+        if subsystem? != "pci" {
+            return None;
+        }
+
+        let device = PciUevent {
+            address: address?,
+            vendor_id: ids.map(|x| x.0),
+            device_id: ids.map(|x| x.1),
+            class_code,
+            driver,
+        };
+
+        match action? {
+            "add" => Some(Self::Added(device)),
+            "remove" => Some(Self::Removed(device)),
+            "bind" => Some(Self::Bound(device)),
+            "unbind" => Some(Self::Unbound(device)),
+            "change" => Some(Self::Changed(device)),
+            _ => None,
+        }
+    }
+}
+
+/// Where a watcher gets its uevents from.
+enum UeventSource {
+    /// A netlink socket subscribed to kernel uevents.
+    Netlink(OwnedFd),
+    /// Uevents written as lines of text.
+    Reader(Box<dyn BufRead + Send>),
+}
+
+/// Watches for PCI devices being added, removed, or changed. Iterating over
+/// a watcher blocks until the next event.
+pub struct PciWatcher {
+    source: UeventSource,
+}
+
+impl PciWatcher {
+    /// Creates a watcher listening for uevents from the kernel.
+    pub fn new() -> Result<Self, PciEnumerationError> {
+        let fd = unsafe {
+            libc::socket(
+                libc::AF_NETLINK,
+                libc::SOCK_DGRAM | libc::SOCK_CLOEXEC,
+                libc::NETLINK_KOBJECT_UEVENT,
+            )
+        };
+        if fd < 0 {
+            return Err(io::Error::last_os_error().into());
+        }
+        // Take ownership right away, so the socket is closed on errors.
+        let socket = unsafe { OwnedFd::from_raw_fd(fd) };
+
+        let mut address: libc::sockaddr_nl = unsafe { mem::zeroed() };
+        address.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+        address.nl_groups = UEVENT_KERNEL_GROUP;
+        let result = unsafe {
+            libc::bind(
+                socket.as_raw_fd(),
+                &address as *const libc::sockaddr_nl as *const libc::sockaddr,
+                mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t,
+            )
+        };
+        if result < 0 {
+            return Err(io::Error::last_os_error().into());
+        }
+
+        Ok(PciWatcher {
+            source: UeventSource::Netlink(socket),
+        })
+    }
+
+    /// Creates a watcher reading uevents from lines of text instead of the
+    /// kernel. Each line holds one `KEY=VALUE` field, and events are
+    /// separated by blank lines.
+    pub fn from_reader<R: BufRead + Send + 'static>(reader: R) -> Self {
+        PciWatcher {
+            source: UeventSource::Reader(Box::new(reader)),
+        }
+    }
+
+    /// Waits for the next PCI event. Returns [`None`] once a reader runs out
+    /// of events; a watcher listening to the kernel never runs out.
+    pub fn next_event(&mut self) -> Option<Result<PciEvent, PciEnumerationError>> {
+        loop {
+            let event = match &mut self.source {
+                UeventSource::Netlink(socket) => receive_uevent(socket),
+                UeventSource::Reader(reader) => read_uevent(reader.as_mut())?,
+            };
+            match event {
+                Ok(Some(event)) => return Some(Ok(event)),
+                Ok(None) => continue,
+                Err(err) => return Some(Err(err)),
+            }
+        }
+    }
+}
+
+impl Iterator for PciWatcher {
+    type Item = Result<PciEvent, PciEnumerationError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_event()
+    }
+}
+
+/// Internal function to receive a uevent from the kernel. The fields of a
+/// uevent are separated by NUL bytes, after a summary header. Messages sent
+/// by other processes rather than the kernel are dropped, so that they can't
+/// fake events.
+fn receive_uevent(socket: &OwnedFd) -> Result<Option<PciEvent>, PciEnumerationError> {
+    let mut buffer = [0u8; UEVENT_BUFFER_SIZE];
+    let mut sender: libc::sockaddr_nl = unsafe { mem::zeroed() };
+    let mut sender_length = mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t;
+    let length = unsafe {
+        libc::recvfrom(
+            socket.as_raw_fd(),
+            buffer.as_mut_ptr() as *mut libc::c_void,
+            buffer.len(),
+            0,
+            &mut sender as *mut libc::sockaddr_nl as *mut libc::sockaddr,
+            &mut sender_length,
+        )
+    };
+    if length < 0 {
+        let err = io::Error::last_os_error();
+        if err.kind() == io::ErrorKind::Interrupted {
+            return Ok(None);
+        }
+        return Err(err.into());
+    }
+    // The kernel sends from port ID 0.
+    if sender.nl_pid != 0 {
+        return Ok(None);
+    }
+
+    let message = String::from_utf8_lossy(&buffer[..length as usize]);
+    Ok(PciEvent::from_fields(message.split('\0')))
+}
+
+/// Internal function to read a uevent from lines of text. Returns [`None`]
+/// at the end of the input.
+fn read_uevent(
+    reader: &mut (dyn BufRead + Send),
+) -> Option<Result<Option<PciEvent>, PciEnumerationError>> {
+    let mut fields: Vec<String> = Vec::new();
+    loop {
+        let mut line = String::new();
+        match reader.read_line(&mut line) {
+            Ok(0) if fields.is_empty() => return None,
+            Ok(0) => break,
+            Ok(_) => {
+                let line = line.trim_end_matches(['\r', '\n']);
+                if line.is_empty() {
+                    if fields.is_empty() {
+                        continue;
+                    }
+                    break;
+                }
+                fields.push(line.to_owned());
+            }
+            Err(err) => return Some(Err(err.into())),
+        }
+    }
+    Some(Ok(PciEvent::from_fields(fields.iter().map(String::as_str))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test reading events from lines of text, skipping anything that is not
+    /// about a PCI device.
+    #[test]
+    fn test_watcher_from_reader() {
+        let input = "\
+add@/devices/pci0000:00/0000:00:03.0
+ACTION=add
+DEVPATH=/devices/pci0000:00/0000:00:03.0
+SUBSYSTEM=pci
+PCI_CLASS=20000
+PCI_ID=8086:100E
+PCI_SUBSYS_ID=8086:001E
+PCI_SLOT_NAME=0000:00:03.0
+
+ACTION=add
+SUBSYSTEM=usb
+DEVPATH=/devices/pci0000:00/0000:00:14.0/usb1
+
+ACTION=bind
+SUBSYSTEM=pci
+DRIVER=e1000
+PCI_SLOT_NAME=0000:00:03.0
+
+ACTION=remove
+SUBSYSTEM=pci
+PCI_SLOT_NAME=0000:00:03.0
+";
+        let events: Vec<PciEvent> = PciWatcher::from_reader(input.as_bytes())
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(events.len(), 3);
+
+        let PciEvent::Added(added) = &events[0] else {
+            panic!("expected an add event, got {:?}", events[0]);
+        };
+        assert_eq!(added.address.to_string(), "0000:00:03.0");
+        assert_eq!(
+            (added.vendor_id, added.device_id),
+            (Some(0x8086), Some(0x100e))
+        );
+        assert_eq!(added.class_code, Some(0x020000));
+
+        assert_eq!(events[1].device().driver.as_deref(), Some("e1000"));
+        assert!(matches!(events[1], PciEvent::Bound(_)));
+        assert!(matches!(events[2], PciEvent::Removed(_)));
+    }
+
+    /// Test subscribing to kernel uevents.
+    #[test]
+    fn test_watcher_netlink() {
+        PciWatcher::new().unwrap();
+    }
+}
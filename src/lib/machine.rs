@@ -0,0 +1,368 @@
+// Copyright (c) 2024 Gibson Pilconis, shibedrill, and contributors
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! # About this module
+//! Scripts often parse the machine-readable output of pciutils' `lspci`
+//! rather than its human-readable one. A [`MachineFormatter`] writes devices
+//! out the same way, with the same quoting and tags, so that those scripts
+//! work unchanged with the `lspci` of `libpci-rs`:
+//! ```rust
+//! # #[cfg(not(any(feature = "pciids-vendors-only", feature = "pciids-no-subsystems")))] {
+//! # use libpci_rs::machine::{MachineDetails, MachineFormat, MachineFormatter};
+//! # use libpci_rs::pci::{PciDeviceAddress, PciDeviceHardware};
+//! let device = PciDeviceHardware {
+//!     address: Some(PciDeviceAddress { domain: 0, bus: 0, device: 0x14, function: 3 }),
+//!     vendor_id: 0x8086,
+//!     device_id: 0xa0f0,
+//!     class: 0x02,
+//!     subclass: 0x80,
+//!     ..Default::default()
+//! };
+//! let formatter = MachineFormatter { format: MachineFormat::Modern, ..Default::default() };
+//! assert_eq!(
+//!     formatter.format(&device, &MachineDetails::default()),
+//!     "00:14.3 \"Network controller\" \"Intel Corporation\" \"Wi-Fi 6 AX201\" \"\" \"\"\n"
+//! );
+//! # }
+//! ```
+
+use crate::names::{NameFormatter, NameStyle};
+use crate::pci::{PciDeviceAddress, PciDeviceHardware};
+
+use std::fmt::Write;
+
+/// A list of the machine-readable formats of `lspci`.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum MachineFormat {
+    /// The backward-compatible format of `lspci -m`. In the verbose format,
+    /// the slot is tagged `Device`, just like the device name.
+    #[default]
+    Compatible,
+    /// The format of `lspci -mm`. In the verbose format, the slot is tagged
+    /// `Slot`.
+    Modern,
+}
+
+/// Details of a device that don't come from its IDs, shown by the verbose
+/// machine-readable format. Details that are missing are left out.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MachineDetails {
+    /// The name of the physical slot the device is in, tagged `PhySlot`.
+    pub phy_slot: Option<String>,
+    /// The driver bound to the device, tagged `Driver`.
+    pub driver: Option<String>,
+    /// The kernel modules able to drive the device, each tagged `Module`.
+    pub modules: Vec<String>,
+    /// The NUMA node the device is attached to, tagged `NUMANode`.
+    pub numa_node: Option<u32>,
+    /// The IOMMU group the device belongs to, tagged `IOMMUGroup`.
+    pub iommu_group: Option<u32>,
+}
+
+/// Formats devices like `lspci -m` and its variants.
+///
+/// | Options  | Formatter                                          |
+/// |----------|----------------------------------------------------|
+/// | `-m`     | [`MachineFormat::Compatible`]                      |
+/// | `-mm`    | [`MachineFormat::Modern`]                          |
+/// | `-vm`    | [`MachineFormat::Compatible`], `verbose`           |
+/// | `-vmm`   | [`MachineFormat::Modern`], `verbose`               |
+/// | `-n`     | [`NameStyle::Numeric`]                             |
+/// | `-nn`    | [`NameStyle::Mixed`]                               |
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct MachineFormatter {
+    /// The machine-readable format to use.
+    pub format: MachineFormat,
+    /// Whether to write each device as a record of tagged lines, rather than
+    /// a single line of quoted fields.
+    pub verbose: bool,
+    /// How names are shown.
+    pub style: NameStyle,
+}
+
+impl MachineFormatter {
+    /// Formats a device, ending with a newline. In the verbose format, the
+    /// record is followed by a blank line, like pciutils does. `details` is
+    /// only used by the verbose format.
+    pub fn format(&self, device: &PciDeviceHardware, details: &MachineDetails) -> String {
+        let names = NameFormatter::new(device, self.style);
+        // Names are only missing in the NoNumbers style.
+        let class = names.class().unwrap_or_default();
+        let vendor = names.vendor().unwrap_or_default();
+        let device_name = names.device().unwrap_or_default();
+        let subsystem = has_subsystem(device).then(|| {
+            (
+                names.subsystem_vendor().unwrap_or_default(),
+                names.subsystem_device().unwrap_or_default(),
+            )
+        });
+
+        let mut output = String::new();
+        if self.verbose {
+            let slot_tag = match self.format {
+                MachineFormat::Compatible => "Device",
+                MachineFormat::Modern => "Slot",
+            };
+            tag(&mut output, slot_tag, slot_name(device.address));
+            tag(&mut output, "Class", class);
+            tag(&mut output, "Vendor", vendor);
+            tag(&mut output, "Device", device_name);
+            if let Some((subsystem_vendor, subsystem_device)) = subsystem {
+                tag(&mut output, "SVendor", subsystem_vendor);
+                tag(&mut output, "SDevice", subsystem_device);
+            }
+            if let Some(phy_slot) = &details.phy_slot {
+                tag(&mut output, "PhySlot", phy_slot);
+            }
+            if device.revision_id != 0 {
+                tag(&mut output, "Rev", format!("{:02x}", device.revision_id));
+            }
+            if device.programming_interface != 0 {
+                tag(
+                    &mut output,
+                    "ProgIf",
+                    format!("{:02x}", device.programming_interface),
+                );
+            }
+            if let Some(driver) = &details.driver {
+                tag(&mut output, "Driver", driver);
+            }
+            for module in &details.modules {
+                tag(&mut output, "Module", module);
+            }
+            if let Some(numa_node) = details.numa_node {
+                tag(&mut output, "NUMANode", numa_node);
+            }
+            if let Some(iommu_group) = details.iommu_group {
+                tag(&mut output, "IOMMUGroup", iommu_group);
+            }
+        } else {
+            output.push_str(&slot_name(device.address));
+            for field in [&class, &vendor, &device_name] {
+                output.push_str(&quoted(field));
+            }
+            if device.revision_id != 0 {
+                let _ = write!(output, " -r{:02x}", device.revision_id);
+            }
+            if device.programming_interface != 0 {
+                let _ = write!(output, " -p{:02x}", device.programming_interface);
+            }
+            match subsystem {
+                Some((subsystem_vendor, subsystem_device)) => {
+                    output.push_str(&quoted(&subsystem_vendor));
+                    output.push_str(&quoted(&subsystem_device));
+                }
+                None => output.push_str(" \"\" \"\""),
+            }
+        }
+        output.push('\n');
+        output
+    }
+}
+
+impl MachineDetails {
+    /// Reads the details of a device from sysfs. Details that can't be read
+    /// are left out. The driver and modules are only read if `kernel` is
+    /// set, like `lspci -k`. Only available on Linux and Android.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn read(address: &PciDeviceAddress, kernel: bool) -> Self {
+        MachineDetails {
+            phy_slot: address.slot().ok().flatten().map(|x| x.name),
+            driver: kernel.then(|| address.driver().ok().flatten()).flatten(),
+            modules: match kernel {
+                true => address.kernel_modules().unwrap_or_default(),
+                false => Vec::new(),
+            },
+            numa_node: address.numa_node().ok().flatten(),
+            iommu_group: address.iommu_group().ok().flatten(),
+        }
+    }
+}
+
+// Whether pciutils shows the subsystem of a device. Subsystem vendor IDs of
+// 0000 and ffff mean there is none, whatever the subsystem ID.
+fn has_subsystem(device: &PciDeviceHardware) -> bool {
+    device.subsys_vendor_id != 0x0000 && device.subsys_vendor_id != 0xffff
+}
+
+// pciutils leaves the domain out when it's 0.
+fn slot_name(address: Option<PciDeviceAddress>) -> String {
+    match address {
+        Some(address) if address.domain == 0 => format!(
+            "{:02x}:{:02x}.{:x}",
+            address.bus, address.device, address.function
+        ),
+        Some(address) => address.to_string(),
+        None => "<address unavailable>".to_string(),
+    }
+}
+
+// Writes a line of the verbose format.
+fn tag<T: std::fmt::Display>(output: &mut String, tag: &str, value: T) {
+    let _ = writeln!(output, "{}:\t{}", tag, value);
+}
+
+// Quotes a field of the single-line format, escaping quotes and backslashes
+// with a backslash, as pciutils does.
+fn quoted(input: &str) -> String {
+    let mut output = String::from(" \"");
+    for char in input.chars() {
+        if char == '"' || char == '\\' {
+            output.push('\\');
+        }
+        output.push(char);
+    }
+    output.push('"');
+    output
+}
+
+// The golden files show device and subsystem names, which the trimming
+// features leave out.
+#[cfg(all(
+    test,
+    not(any(feature = "pciids-vendors-only", feature = "pciids-no-subsystems"))
+))]
+mod tests {
+    use crate::machine::{quoted, MachineDetails, MachineFormat, MachineFormatter};
+    use crate::names::NameStyle;
+    use crate::pci::{PciDeviceAddress, PciDeviceHardware};
+
+    // A few devices covering known and unknown names, subsystems, and
+    // addresses with and without a domain. The unknown IDs are missing from
+    // the upstream pci.ids as well as the copy in the tree.
+    fn devices() -> Vec<(PciDeviceHardware, MachineDetails)> {
+        let address = |domain, bus, device, function| {
+            Some(PciDeviceAddress {
+                domain,
+                bus,
+                device,
+                function,
+            })
+        };
+        vec![
+            (
+                PciDeviceHardware {
+                    address: address(0, 0x00, 0x14, 3),
+                    vendor_id: 0x8086,
+                    device_id: 0xa0f0,
+                    subsys_vendor_id: 0x8086,
+                    subsys_device_id: 0x0070,
+                    class: 0x02,
+                    subclass: 0x80,
+                    programming_interface: 0x00,
+                    revision_id: 0x20,
+                },
+                MachineDetails {
+                    driver: Some("iwlwifi".to_string()),
+                    modules: vec!["iwlwifi".to_string()],
+                    iommu_group: Some(12),
+                    ..Default::default()
+                },
+            ),
+            (
+                PciDeviceHardware {
+                    address: address(1, 0x3b, 0x00, 1),
+                    vendor_id: 0x1af4,
+                    device_id: 0x1042,
+                    subsys_vendor_id: 0x1af4,
+                    subsys_device_id: 0x7f02,
+                    class: 0x01,
+                    subclass: 0x7f,
+                    programming_interface: 0x00,
+                    revision_id: 0x01,
+                },
+                MachineDetails {
+                    phy_slot: Some("7".to_string()),
+                    driver: Some("virtio-pci".to_string()),
+                    modules: vec!["virtio_pci".to_string(), "virtio_blk".to_string()],
+                    numa_node: Some(0),
+                    ..Default::default()
+                },
+            ),
+            (
+                PciDeviceHardware {
+                    address: address(0, 0x00, 0x1f, 0),
+                    vendor_id: 0x0002,
+                    device_id: 0x0001,
+                    class: 0x0c,
+                    subclass: 0x03,
+                    programming_interface: 0x30,
+                    ..Default::default()
+                },
+                MachineDetails::default(),
+            ),
+            (
+                PciDeviceHardware {
+                    address: address(0, 0x00, 0x03, 0),
+                    vendor_id: 0x8086,
+                    device_id: 0x100e,
+                    subsys_vendor_id: 0x0000,
+                    subsys_device_id: 0x0001,
+                    class: 0x02,
+                    subclass: 0x00,
+                    programming_interface: 0x00,
+                    revision_id: 0x03,
+                },
+                MachineDetails::default(),
+            ),
+        ]
+    }
+
+    fn listing(formatter: MachineFormatter) -> String {
+        devices()
+            .iter()
+            .map(|(device, details)| formatter.format(device, details))
+            .collect()
+    }
+
+    fn golden(name: &str) -> String {
+        let path = format!("{}/tests/golden/{}", env!("CARGO_MANIFEST_DIR"), name);
+        std::fs::read_to_string(path).unwrap()
+    }
+
+    /// Test the single-line format against golden files.
+    #[test]
+    fn test_machine_format() {
+        let formatter = MachineFormatter::default();
+        assert_eq!(listing(formatter), golden("lspci-m.txt"));
+        // The formats only differ in the verbose format.
+        let formatter = MachineFormatter {
+            format: MachineFormat::Modern,
+            ..formatter
+        };
+        assert_eq!(listing(formatter), golden("lspci-m.txt"));
+        let formatter = MachineFormatter {
+            style: NameStyle::Mixed,
+            ..formatter
+        };
+        assert_eq!(listing(formatter), golden("lspci-mmnn.txt"));
+    }
+
+    /// Test the verbose format against golden files.
+    #[test]
+    fn test_verbose_machine_format() {
+        let formatter = MachineFormatter {
+            verbose: true,
+            ..Default::default()
+        };
+        assert_eq!(listing(formatter), golden("lspci-vm.txt"));
+        let formatter = MachineFormatter {
+            format: MachineFormat::Modern,
+            ..formatter
+        };
+        assert_eq!(listing(formatter), golden("lspci-vmm.txt"));
+        let formatter = MachineFormatter {
+            style: NameStyle::Numeric,
+            ..formatter
+        };
+        assert_eq!(listing(formatter), golden("lspci-vmmn.txt"));
+    }
+
+    /// Test quoting fields of the single-line format.
+    #[test]
+    fn test_quoting() {
+        assert_eq!(quoted("Plain"), " \"Plain\"");
+        assert_eq!(quoted("5\" \\ Bay"), " \"5\\\" \\\\ Bay\"");
+        assert_eq!(quoted(""), " \"\"");
+    }
+}
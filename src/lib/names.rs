@@ -0,0 +1,405 @@
+// Copyright (c) 2024 Gibson Pilconis, shibedrill, and contributors
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! # About this module
+//! A [`NameFormatter`] turns the IDs of a device into the strings pciutils'
+//! `pci_lookup_name` makes, falling back to numbers for anything missing
+//! from the database the same way. The [`NameStyle`] picks between names,
+//! numbers, or both, like the `PCI_LOOKUP_*` flags and the `-n` option of
+//! `lspci`:
+//! ```rust
+//! # #[cfg(not(any(feature = "pciids-vendors-only", feature = "pciids-no-subsystems")))] {
+//! # use libpci_rs::names::{NameFormatter, NameStyle};
+//! # use libpci_rs::pci::PciDeviceHardware;
+//! let device = PciDeviceHardware {
+//!     vendor_id: 0x8086,
+//!     device_id: 0xa0f0,
+//!     ..Default::default()
+//! };
+//! let names = NameFormatter::new(&device, NameStyle::Mixed);
+//! assert_eq!(
+//!     names.vendor_device().unwrap(),
+//!     "Intel Corporation Wi-Fi 6 AX201 [8086:a0f0]"
+//! );
+//! # }
+//! ```
+
+use crate::pci::{DeviceNames, PciDeviceHardware};
+
+/// A list of the ways [`NameFormatter`] can format names.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum NameStyle {
+    /// Names, falling back to numbers for IDs that aren't in the database,
+    /// like `Intel Corporation Device 0d57`. This is what `lspci` prints.
+    #[default]
+    Names,
+    /// Numbers only, like `8086:0d57`. Corresponds to `PCI_LOOKUP_NUMERIC`
+    /// and `lspci -n`.
+    Numeric,
+    /// Names followed by numbers in brackets, like
+    /// `Intel Corporation Device [8086:0d57]`. Corresponds to
+    /// `PCI_LOOKUP_MIXED` and `lspci -nn`.
+    Mixed,
+    /// Names only, without falling back to numbers. Names that aren't in the
+    /// database are [`None`]. Corresponds to `PCI_LOOKUP_NO_NUMBERS`.
+    NoNumbers,
+}
+
+/// Formats the names of a device in a given [`NameStyle`]. The names are
+/// looked up once, when the formatter is made.
+#[derive(Debug, Clone)]
+pub struct NameFormatter<'a> {
+    device: &'a PciDeviceHardware,
+    names: DeviceNames,
+    style: NameStyle,
+}
+
+impl<'a> NameFormatter<'a> {
+    /// Looks up the names of a device, to be formatted in the given style.
+    pub fn new(device: &'a PciDeviceHardware, style: NameStyle) -> Self {
+        NameFormatter {
+            device,
+            names: device.names(),
+            style,
+        }
+    }
+
+    /// Formats the vendor name, like `Intel Corporation` or `Vendor 8086`.
+    pub fn vendor(&self) -> Option<String> {
+        let number = format!("{:04x}", self.device.vendor_id);
+        self.single(self.names.vendor, &number, "Vendor")
+    }
+
+    /// Formats the device name, like `Wi-Fi 6 AX201` or `Device a0f0`.
+    pub fn device(&self) -> Option<String> {
+        let number = format!("{:04x}", self.device.device_id);
+        self.single(self.names.device, &number, "Device")
+    }
+
+    /// Formats the vendor and device names together, like
+    /// `Intel Corporation Wi-Fi 6 AX201` or `Intel Corporation Device a0f0`.
+    pub fn vendor_device(&self) -> Option<String> {
+        let number = format!(
+            "{:04x}:{:04x}",
+            self.device.vendor_id, self.device.device_id
+        );
+        self.pair(self.names.vendor, self.names.device, &number)
+    }
+
+    /// Formats the subsystem vendor name, like `Dell` or
+    /// `Unknown vendor 1028`.
+    pub fn subsystem_vendor(&self) -> Option<String> {
+        let number = format!("{:04x}", self.device.subsys_vendor_id);
+        self.single(self.names.subsystem_vendor, &number, "Unknown vendor")
+    }
+
+    /// Formats the subsystem name, like `Wi-Fi 6 AX201 160MHz` or
+    /// `Device 0070`.
+    pub fn subsystem_device(&self) -> Option<String> {
+        let number = format!("{:04x}", self.device.subsys_device_id);
+        self.single(self.names.subsystem, &number, "Device")
+    }
+
+    /// Formats the subsystem vendor and subsystem names together, like
+    /// `Intel Corporation Wi-Fi 6 AX201 160MHz` or
+    /// `Intel Corporation Device 0070`.
+    pub fn subsystem(&self) -> Option<String> {
+        let number = format!(
+            "{:04x}:{:04x}",
+            self.device.subsys_vendor_id, self.device.subsys_device_id
+        );
+        self.pair(self.names.subsystem_vendor, self.names.subsystem, &number)
+    }
+
+    /// Formats the class of the device, like `USB controller`. If only the
+    /// class is known and not the subclass, the class name is followed by the
+    /// full number, like `Bridge [06ff]`.
+    pub fn class(&self) -> Option<String> {
+        let number = format!("{:02x}{:02x}", self.device.class, self.device.subclass);
+        match (self.names.subclass, self.names.class) {
+            (None, Some(class)) if self.style != NameStyle::Numeric => {
+                Some(format!("{} [{}]", class, number))
+            }
+            (subclass, _) => self.single(subclass, &number, "Class"),
+        }
+    }
+
+    /// Formats the programming interface of the device, like `XHCI` or
+    /// `ProgIf 30`.
+    pub fn prog_if(&self) -> Option<String> {
+        let number = format!("{:02x}", self.device.programming_interface);
+        match self.names.prog_if {
+            Some(name) => self.single(Some(name), &number, "ProgIf"),
+            None => self.single(self.ide_prog_if().as_deref(), &number, "ProgIf"),
+        }
+    }
+
+    // IDE controllers describe their modes with bits of the programming
+    // interface, which pciutils spells out when the database doesn't.
+    fn ide_prog_if(&self) -> Option<String> {
+        let prog_if = self.device.programming_interface;
+        if (self.device.class, self.device.subclass) != (0x01, 0x01) || prog_if & 0x70 != 0 {
+            return None;
+        }
+        let flags = [
+            (0x80, "Master"),
+            (0x08, "SecP"),
+            (0x04, "SecO"),
+            (0x02, "PriP"),
+            (0x01, "PriO"),
+        ];
+        let modes: Vec<&str> = flags
+            .iter()
+            .filter(|(bit, _)| prog_if & bit != 0)
+            .map(|(_, mode)| *mode)
+            .collect();
+        Some(modes.join(" "))
+    }
+
+    // Formats a single name, or `unknown` and the number if it's missing.
+    fn single(&self, name: Option<&str>, number: &str, unknown: &str) -> Option<String> {
+        match (self.style, name) {
+            (NameStyle::NoNumbers, None) => None,
+            (NameStyle::Numeric, _) => Some(number.to_string()),
+            (NameStyle::Mixed, Some(name)) => Some(format!("{} [{}]", name, number)),
+            (NameStyle::Mixed, None) => Some(format!("{} [{}]", unknown, number)),
+            (_, Some(name)) => Some(name.to_string()),
+            (_, None) => Some(format!("{} {}", unknown, number)),
+        }
+    }
+
+    // Formats a vendor and device name together. `number` holds both IDs,
+    // separated by a colon.
+    fn pair(&self, vendor: Option<&str>, device: Option<&str>, number: &str) -> Option<String> {
+        let device_number = number.split_once(':').map_or(number, |x| x.1);
+        match (self.style, vendor, device) {
+            (NameStyle::NoNumbers, Some(vendor), Some(device)) => {
+                Some(format!("{} {}", vendor, device))
+            }
+            (NameStyle::NoNumbers, _, _) => None,
+            (NameStyle::Numeric, _, _) => Some(number.to_string()),
+            (NameStyle::Mixed, Some(vendor), Some(device)) => {
+                Some(format!("{} {} [{}]", vendor, device, number))
+            }
+            (NameStyle::Mixed, Some(vendor), None) => {
+                Some(format!("{} Device [{}]", vendor, number))
+            }
+            (NameStyle::Mixed, None, _) => Some(format!("Device [{}]", number)),
+            (NameStyle::Names, Some(vendor), Some(device)) => {
+                Some(format!("{} {}", vendor, device))
+            }
+            (NameStyle::Names, Some(vendor), None) => {
+                Some(format!("{} Device {}", vendor, device_number))
+            }
+            (NameStyle::Names, None, _) => Some(format!("Device {}", number)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::names::{NameFormatter, NameStyle};
+    use crate::pci::{DeviceNames, PciDeviceHardware};
+
+    // Formats every name of a device in every style, in the order Names,
+    // Numeric, Mixed, NoNumbers.
+    #[cfg(not(any(feature = "pciids-vendors-only", feature = "pciids-no-subsystems")))]
+    fn formatted<'a, F>(device: &'a PciDeviceHardware, name: F) -> [Option<String>; 4]
+    where
+        F: Fn(&NameFormatter<'a>) -> Option<String>,
+    {
+        formatted_with(device, device.names(), name)
+    }
+
+    // Like `formatted`, but with the given names rather than the ones in the
+    // database, so that the result doesn't depend on which pci.ids the
+    // library is built from.
+    fn formatted_with<'a, F>(
+        device: &'a PciDeviceHardware,
+        names: DeviceNames,
+        name: F,
+    ) -> [Option<String>; 4]
+    where
+        F: Fn(&NameFormatter<'a>) -> Option<String>,
+    {
+        [
+            NameStyle::Names,
+            NameStyle::Numeric,
+            NameStyle::Mixed,
+            NameStyle::NoNumbers,
+        ]
+        .map(|style| {
+            name(&NameFormatter {
+                device,
+                names,
+                style,
+            })
+        })
+    }
+
+    fn strings<const N: usize>(expected: [Option<&str>; N]) -> [Option<String>; N] {
+        expected.map(|x| x.map(str::to_string))
+    }
+
+    /// Test formatting the names of a device that is in the database.
+    #[cfg(not(any(feature = "pciids-vendors-only", feature = "pciids-no-subsystems")))]
+    #[test]
+    fn test_known_names() {
+        let device = PciDeviceHardware {
+            vendor_id: 0x8086,
+            device_id: 0xa0f0,
+            subsys_vendor_id: 0x8086,
+            subsys_device_id: 0x0070,
+            class: 0x0c,
+            subclass: 0x03,
+            programming_interface: 0x30,
+            ..Default::default()
+        };
+        assert_eq!(
+            formatted(&device, NameFormatter::vendor_device),
+            strings([
+                Some("Intel Corporation Wi-Fi 6 AX201"),
+                Some("8086:a0f0"),
+                Some("Intel Corporation Wi-Fi 6 AX201 [8086:a0f0]"),
+                Some("Intel Corporation Wi-Fi 6 AX201"),
+            ])
+        );
+        assert_eq!(
+            formatted(&device, NameFormatter::subsystem),
+            strings([
+                Some("Intel Corporation Wi-Fi 6 AX201 160MHz"),
+                Some("8086:0070"),
+                Some("Intel Corporation Wi-Fi 6 AX201 160MHz [8086:0070]"),
+                Some("Intel Corporation Wi-Fi 6 AX201 160MHz"),
+            ])
+        );
+        assert_eq!(
+            formatted(&device, NameFormatter::class),
+            strings([
+                Some("USB controller"),
+                Some("0c03"),
+                Some("USB controller [0c03]"),
+                Some("USB controller"),
+            ])
+        );
+        assert_eq!(
+            formatted(&device, NameFormatter::prog_if),
+            strings([Some("XHCI"), Some("30"), Some("XHCI [30]"), Some("XHCI")])
+        );
+    }
+
+    /// Test the fallbacks for names that aren't in the database.
+    #[test]
+    fn test_unknown_names() {
+        let mut device = PciDeviceHardware {
+            vendor_id: 0x8086,
+            device_id: 0x0d57,
+            subsys_vendor_id: 0x4321,
+            subsys_device_id: 0x0001,
+            class: 0x06,
+            subclass: 0xff,
+            ..Default::default()
+        };
+        // Only the vendor and the class are known.
+        let mut names = DeviceNames {
+            vendor: Some("Intel Corporation"),
+            class: Some("Bridge"),
+            ..Default::default()
+        };
+        assert_eq!(
+            formatted_with(&device, names, NameFormatter::vendor_device),
+            strings([
+                Some("Intel Corporation Device 0d57"),
+                Some("8086:0d57"),
+                Some("Intel Corporation Device [8086:0d57]"),
+                None,
+            ])
+        );
+        assert_eq!(
+            formatted_with(&device, names, NameFormatter::device),
+            strings([
+                Some("Device 0d57"),
+                Some("0d57"),
+                Some("Device [0d57]"),
+                None
+            ])
+        );
+        assert_eq!(
+            formatted_with(&device, names, NameFormatter::subsystem_vendor),
+            strings([
+                Some("Unknown vendor 4321"),
+                Some("4321"),
+                Some("Unknown vendor [4321]"),
+                None,
+            ])
+        );
+        assert_eq!(
+            formatted_with(&device, names, NameFormatter::subsystem),
+            strings([
+                Some("Device 4321:0001"),
+                Some("4321:0001"),
+                Some("Device [4321:0001]"),
+                None,
+            ])
+        );
+        // Only the class is known, so its name comes with the number.
+        assert_eq!(
+            formatted_with(&device, names, NameFormatter::class),
+            strings([
+                Some("Bridge [06ff]"),
+                Some("06ff"),
+                Some("Bridge [06ff]"),
+                Some("Bridge [06ff]"),
+            ])
+        );
+
+        // Nothing is known.
+        device.vendor_id = 0x4321;
+        device.class = 0x0e;
+        device.subclass = 0x00;
+        names = DeviceNames::default();
+        assert_eq!(
+            formatted_with(&device, names, NameFormatter::vendor),
+            strings([
+                Some("Vendor 4321"),
+                Some("4321"),
+                Some("Vendor [4321]"),
+                None
+            ])
+        );
+        assert_eq!(
+            formatted_with(&device, names, NameFormatter::vendor_device),
+            strings([
+                Some("Device 4321:0d57"),
+                Some("4321:0d57"),
+                Some("Device [4321:0d57]"),
+                None,
+            ])
+        );
+        assert_eq!(
+            formatted_with(&device, names, NameFormatter::class),
+            strings([Some("Class 0e00"), Some("0e00"), Some("Class [0e00]"), None])
+        );
+    }
+
+    /// Test spelling out the programming interfaces of IDE controllers.
+    #[test]
+    fn test_ide_prog_if() {
+        let mut device = PciDeviceHardware {
+            class: 0x01,
+            subclass: 0x01,
+            programming_interface: 0x8e,
+            ..Default::default()
+        };
+        let prog_if =
+            |device: &PciDeviceHardware| NameFormatter::new(device, NameStyle::Names).prog_if();
+        assert_eq!(prog_if(&device).unwrap(), "Master SecP SecO PriP");
+        device.programming_interface = 0x80;
+        assert_eq!(
+            prog_if(&device).unwrap(),
+            "ISA Compatibility mode-only controller, supports bus mastering"
+        );
+        device.programming_interface = 0x90;
+        assert_eq!(prog_if(&device).unwrap(), "ProgIf 90");
+    }
+}
@@ -61,7 +61,7 @@ impl TryFrom<String> for PciDeviceAddress {
 }
 
 /// A struct representing a PCI device.
-/// 
+///
 /// # Fields
 /// This structure contains not only all the hardcoded information within the
 /// device, but also its location on the system PCI bus, and it has some
@@ -136,55 +136,102 @@ impl Display for PciDeviceHardware {
     }
 }
 
+/// The names of a device and of its class, as found by
+/// [`PciDeviceHardware::names`]. Each name is [`None`] if it isn't in the
+/// database. Requires the [pciids feature](crate#pciids).
+#[cfg(feature = "pciids")]
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct DeviceNames {
+    /// The name of the vendor.
+    pub vendor: Option<&'static str>,
+    /// The name of the device.
+    pub device: Option<&'static str>,
+    /// The name of the subsystem vendor.
+    pub subsystem_vendor: Option<&'static str>,
+    /// The name of the subsystem, if it is listed under the device.
+    pub subsystem: Option<&'static str>,
+    /// The description of the device class.
+    pub class: Option<&'static str>,
+    /// The description of the device subclass.
+    pub subclass: Option<&'static str>,
+    /// The description of the device programming interface.
+    pub prog_if: Option<&'static str>,
+}
+
 /// All of the following methods in this block require the
 /// [pciids feature](crate#pciids).
 #[cfg(feature = "pciids")]
 impl PciDeviceHardware {
+    /// Get every name of the device at once. This looks the vendor and the
+    /// class up only once, so it is cheaper than calling the methods for the
+    /// individual names, and the names don't need to be copied.
+    pub fn names(&self) -> DeviceNames {
+        let vendor = lookup_vendor(self.vendor_id);
+        let device = vendor.as_ref().and_then(|x| x.device(self.device_id));
+
+        // A subsystem vendor ID of 0000 or ffff means there is no subsystem.
+        let (subsystem_vendor, subsystem) = match self.subsys_vendor_id {
+            0x0000 | 0xffff => (None, None),
+            subvendor => (
+                match subvendor == self.vendor_id {
+                    true => vendor.map(|x| x.name()),
+                    false => lookup_vendor(subvendor).map(|x| x.name()),
+                },
+                device
+                    .and_then(|x| x.subsystem(self.subsys_device_id, subvendor))
+                    .map(|x| x.name()),
+            ),
+        };
+
+        let class = lookup_class(self.class);
+        let subclass = class.as_ref().and_then(|x| x.subclass(self.subclass));
+        let prog_if = subclass.and_then(|x| x.prog(self.programming_interface));
+
+        DeviceNames {
+            vendor: vendor.map(|x| x.name()),
+            device: device.map(|x| x.name()),
+            subsystem_vendor,
+            subsystem,
+            class: class.map(|x| x.name()),
+            subclass: subclass.map(|x| x.name()),
+            prog_if: prog_if.map(|x| x.name()),
+        }
+    }
     /// Get the pretty name of the device.
     pub fn device_name(&self) -> Option<String> {
-        Some(
-            lookup_vendor(self.vendor_id)?
-                .device(self.device_id)?
-                .name()
-                .to_owned(),
-        )
+        self.names().device.map(str::to_owned)
     }
     /// Get the pretty name of the vendor.
     pub fn vendor_name(&self) -> Option<String> {
-        Some(lookup_vendor(self.vendor_id)?.name().to_owned())
+        self.names().vendor.map(str::to_owned)
     }
     /// Get the description of the device class.
     pub fn class_name(&self) -> Option<String> {
-        Some(lookup_class(self.class)?.name().to_owned())
+        self.names().class.map(str::to_owned)
     }
     /// Get the description of the device subclass.
     pub fn subclass_name(&self) -> Option<String> {
-        Some(
-            lookup_class(self.class)?
-                .subclass(self.subclass)?
-                .name()
-                .to_owned(),
-        )
+        self.names().subclass.map(str::to_owned)
     }
     /// Get the description of the device programming interface.
     pub fn progint_name(&self) -> Option<String> {
-        Some(
-            lookup_class(self.class)?
-                .subclass(self.subclass)?
-                .prog(self.programming_interface)?
-                .name()
-                .to_owned(),
-        )
+        self.names().prog_if.map(str::to_owned)
     }
-    /// Get the pretty name of the subdevice.
+    /// Get the pretty name of the subdevice. If the subdevice isn't listed
+    /// under the device, it is named after its vendor instead, like
+    /// `Dell Device 0a2b`. See [`lookup_subsystem`].
     pub fn subdevice_name(&self) -> Option<String> {
-        Some(
-            lookup_vendor(self.vendor_id)?
-                .device(self.device_id)?
-                .subsystem(self.subsys_device_id, self.subsys_vendor_id)?
-                .name()
-                .to_owned(),
-        )
+        let names = self.names();
+        if names.subsystem_vendor.is_none() && names.subsystem.is_none() {
+            return None;
+        }
+        let name = SubsystemName {
+            subvendor_id: self.subsys_vendor_id,
+            subdevice_id: self.subsys_device_id,
+            vendor_name: names.subsystem_vendor,
+            name: names.subsystem,
+        };
+        Some(name.to_string())
     }
     /// Get a pretty representation of the entire device. This method does a
     /// lot of its own error handling, so if you want to handle things in
@@ -201,6 +248,7 @@ impl PciDeviceHardware {
     /// Unless the address is not [available](crate::pci::PciDeviceHardware#availability):  
     /// `[address inaccessible] Bridge: Red Hat, Inc. Virtio 1.0 socket  (rev 01)`  
     pub fn pretty_print(&self) -> Option<String> {
+        let names = self.names();
         Some(format!(
             "{} {}: {} {} {}",
             if let Some(address) = self.address {
@@ -208,9 +256,9 @@ impl PciDeviceHardware {
             } else {
                 "[address inaccessible]".to_owned()
             },
-            self.subclass_name()?,
-            self.vendor_name()?,
-            self.device_name()?,
+            names.subclass?,
+            names.vendor?,
+            names.device?,
             {
                 if self.revision_id != 0 {
                     format!(" (rev {:02x})", self.revision_id)
@@ -231,6 +279,7 @@ use crate::pci::PciInformationError::{PermissionDenied, Unavailable, Unknown};
 
 /// A list of errors that can occur while enumerating PCI devices.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum PciEnumerationError {
     /// Error interfacing with OS APIs.
     OsError,
@@ -244,6 +293,8 @@ pub enum PciEnumerationError {
     PermissionDenied,
     /// Attribute is not valid hex.
     ParseInt(ParseIntError),
+    /// Attribute holds a value libpci-rs does not know how to interpret.
+    InvalidAttribute(String),
     /// An error that couldn't be resolved originating from a foreign backend.
     GenericForeignError,
 }
@@ -260,6 +311,7 @@ impl Display for PciEnumerationError {
                 Self::NotFound => "NotFound",
                 Self::PermissionDenied => "PermissionDenied",
                 Self::ParseInt(_parserr) => "ParseIntError",
+                Self::InvalidAttribute(_value) => "InvalidAttribute",
                 Self::GenericForeignError => "GenericForeignError",
             }
         )
@@ -311,6 +363,12 @@ impl From<u8> for PciInformationError {
 
 #[cfg(test)]
 mod tests {
+    #[cfg(all(
+        feature = "pciids",
+        not(any(feature = "pciids-vendors-only", feature = "pciids-no-subsystems"))
+    ))]
+    use crate::pci::{DeviceNames, PciDeviceHardware};
+
     #[test]
     fn test_pci_listing() {
         println!("Begin test output: test_pci_listing");
@@ -320,4 +378,53 @@ mod tests {
         }
         println!("End test output: test_pci_listing");
     }
+
+    /// Test that every name of a device is looked up at once.
+    #[cfg(all(
+        feature = "pciids",
+        not(any(feature = "pciids-vendors-only", feature = "pciids-no-subsystems"))
+    ))]
+    #[test]
+    fn test_device_names() {
+        let mut device = PciDeviceHardware {
+            vendor_id: 0x8086,
+            device_id: 0xa0f0,
+            subsys_vendor_id: 0x8086,
+            subsys_device_id: 0x0070,
+            class: 0x0c,
+            subclass: 0x03,
+            programming_interface: 0x30,
+            ..Default::default()
+        };
+        assert_eq!(
+            device.names(),
+            DeviceNames {
+                vendor: Some("Intel Corporation"),
+                device: Some("Wi-Fi 6 AX201"),
+                subsystem_vendor: Some("Intel Corporation"),
+                subsystem: Some("Wi-Fi 6 AX201 160MHz"),
+                class: Some("Serial bus controller"),
+                subclass: Some("USB controller"),
+                prog_if: Some("XHCI"),
+            }
+        );
+        assert_eq!(device.subdevice_name().unwrap(), "Wi-Fi 6 AX201 160MHz");
+
+        // An unlisted subsystem is named after its vendor.
+        device.subsys_device_id = 0xfff0;
+        assert_eq!(device.names().subsystem, None);
+        assert_eq!(
+            device.subdevice_name().unwrap(),
+            "Intel Corporation Device fff0"
+        );
+
+        // A subsystem vendor of 0000 means there is no subsystem.
+        device.subsys_vendor_id = 0x0000;
+        assert_eq!(device.names().subsystem_vendor, None);
+        assert_eq!(device.subdevice_name(), None);
+
+        device.programming_interface = 0xff;
+        assert_eq!(device.names().prog_if, None);
+        assert_eq!(device.subclass_name().unwrap(), "USB controller");
+    }
 }
@@ -0,0 +1,379 @@
+// Copyright (c) 2024 Gibson Pilconis, shibedrill, and contributors
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! # About this module
+//! A snapshot records every PCI device in the system at a point in time, so
+//! you can compare the system before and after a firmware update, a reboot,
+//! or a hardware swap, and find anything that disappeared, appeared, or
+//! renegotiated its link:
+//! ```rust
+//! # use libpci_rs::snapshot::{diff, PciSnapshot};
+//! let before = PciSnapshot::capture().unwrap();
+//! // Update the firmware here.
+//! let after = PciSnapshot::capture().unwrap();
+//! let changes = diff(&before, &after);
+//! for device in &changes.removed {
+//!     println!("{} disappeared", device.hardware);
+//! }
+//! for device in &changes.changed {
+//!     println!("{:?} changed: {:?}", device.address, device.changes);
+//! }
+//! ```
+//! Devices are matched up by their address. On platforms where the address
+//! is not [available](crate::pci::PciDeviceHardware#availability), devices
+//! can only be reported as added or removed. The driver and link of a device
+//! are only recorded on Linux.
+
+use crate::config::PciConfigSpace;
+use crate::pci::{get_pci_list, PciDeviceAddress, PciDeviceHardware, PciEnumerationError};
+
+use std::collections::BTreeMap;
+use std::time::SystemTime;
+
+/// The state of a device when a snapshot was taken.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PciSnapshotDevice {
+    /// The device itself.
+    pub hardware: PciDeviceHardware,
+    /// The driver bound to the device, if any.
+    pub driver: Option<String>,
+    /// The negotiated speed of the link, if the device is a PCI Express
+    /// device.
+    pub link_speed: Option<String>,
+    /// The negotiated width of the link, if the device is a PCI Express
+    /// device.
+    pub link_width: Option<u8>,
+    /// The configuration space of the device, if the snapshot includes it.
+    pub config: Option<PciConfigSpace>,
+}
+
+/// Every PCI device in the system at a point in time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PciSnapshot {
+    /// When the snapshot was taken.
+    pub taken_at: SystemTime,
+    /// The devices in the system, sorted.
+    pub devices: Vec<PciSnapshotDevice>,
+}
+
+/// A field of a device that changed between two snapshots, with its old and
+/// new value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PciFieldChange {
+    /// The vendor ID changed.
+    VendorId(u16, u16),
+    /// The device ID changed.
+    DeviceId(u16, u16),
+    /// The subsystem vendor ID changed.
+    SubsystemVendorId(u16, u16),
+    /// The subsystem device ID changed.
+    SubsystemDeviceId(u16, u16),
+    /// The class, subclass, or programming interface changed. The values
+    /// hold all three.
+    ClassCode(u32, u32),
+    /// The revision changed.
+    Revision(u8, u8),
+    /// The driver bound to the device changed.
+    Driver(Option<String>, Option<String>),
+    /// The link speed changed.
+    LinkSpeed(Option<String>, Option<String>),
+    /// The link width changed.
+    LinkWidth(Option<u8>, Option<u8>),
+    /// The configuration space changed. Only reported when both snapshots
+    /// include it.
+    ConfigSpace,
+}
+
+/// A device that is present in both snapshots, but changed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PciDeviceChange {
+    /// The address of the device.
+    pub address: PciDeviceAddress,
+    /// The fields that changed.
+    pub changes: Vec<PciFieldChange>,
+}
+
+/// The differences between two snapshots.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PciSnapshotDiff {
+    /// Devices that are only in the new snapshot.
+    pub added: Vec<PciSnapshotDevice>,
+    /// Devices that are only in the old snapshot.
+    pub removed: Vec<PciSnapshotDevice>,
+    /// Devices that are in both snapshots, but changed.
+    pub changed: Vec<PciDeviceChange>,
+}
+
+impl PciSnapshotDiff {
+    /// Returns true if the snapshots are the same.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Internal function to pack the class, subclass, and programming interface
+/// of a device into one class code.
+fn class_code(device: &PciDeviceHardware) -> u32 {
+    (device.class as u32) << 16
+        | (device.subclass as u32) << 8
+        | device.programming_interface as u32
+}
+
+impl PciSnapshotDevice {
+    /// Records the state of a device, optionally including its
+    /// configuration space.
+    fn capture(hardware: PciDeviceHardware, with_config: bool) -> Self {
+        let device = PciSnapshotDevice::from(hardware);
+
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        let device = {
+            let mut device = device;
+            if let Some(address) = device.hardware.address {
+                device.driver = address.driver().ok().flatten();
+                device.link_speed = address.current_link_speed().ok().flatten();
+                device.link_width = address.current_link_width().ok().flatten();
+                if with_config {
+                    device.config = address.config_space().ok();
+                }
+            }
+            device
+        };
+        #[cfg(not(any(target_os = "linux", target_os = "android")))]
+        let _ = with_config;
+
+        device
+    }
+
+    /// Internal function to list every field that differs from another
+    /// recording of the same device.
+    fn changes(&self, new: &PciSnapshotDevice) -> Vec<PciFieldChange> {
+        let (old_hw, new_hw) = (&self.hardware, &new.hardware);
+        let mut changes: Vec<PciFieldChange> = Vec::new();
+
+        if old_hw.vendor_id != new_hw.vendor_id {
+            changes.push(PciFieldChange::VendorId(old_hw.vendor_id, new_hw.vendor_id));
+        }
+        if old_hw.device_id != new_hw.device_id {
+            changes.push(PciFieldChange::DeviceId(old_hw.device_id, new_hw.device_id));
+        }
+        if old_hw.subsys_vendor_id != new_hw.subsys_vendor_id {
+            changes.push(PciFieldChange::SubsystemVendorId(
+                old_hw.subsys_vendor_id,
+                new_hw.subsys_vendor_id,
+            ));
+        }
+        if old_hw.subsys_device_id != new_hw.subsys_device_id {
+            changes.push(PciFieldChange::SubsystemDeviceId(
+                old_hw.subsys_device_id,
+                new_hw.subsys_device_id,
+            ));
+        }
+        if class_code(old_hw) != class_code(new_hw) {
+            changes.push(PciFieldChange::ClassCode(
+                class_code(old_hw),
+                class_code(new_hw),
+            ));
+        }
+        if old_hw.revision_id != new_hw.revision_id {
+            changes.push(PciFieldChange::Revision(
+                old_hw.revision_id,
+                new_hw.revision_id,
+            ));
+        }
+        if self.driver != new.driver {
+            changes.push(PciFieldChange::Driver(
+                self.driver.clone(),
+                new.driver.clone(),
+            ));
+        }
+        if self.link_speed != new.link_speed {
+            changes.push(PciFieldChange::LinkSpeed(
+                self.link_speed.clone(),
+                new.link_speed.clone(),
+            ));
+        }
+        if self.link_width != new.link_width {
+            changes.push(PciFieldChange::LinkWidth(self.link_width, new.link_width));
+        }
+        if let (Some(old_config), Some(new_config)) = (&self.config, &new.config) {
+            if old_config != new_config {
+                changes.push(PciFieldChange::ConfigSpace);
+            }
+        }
+
+        changes
+    }
+}
+
+// A device with nothing but its hardware information recorded.
+impl From<PciDeviceHardware> for PciSnapshotDevice {
+    fn from(hardware: PciDeviceHardware) -> Self {
+        PciSnapshotDevice {
+            hardware,
+            driver: None,
+            link_speed: None,
+            link_width: None,
+            config: None,
+        }
+    }
+}
+
+impl PciSnapshot {
+    /// Takes a snapshot of every device in the system.
+    pub fn capture() -> Result<Self, PciEnumerationError> {
+        Self::capture_devices(false)
+    }
+
+    /// Takes a snapshot of every device in the system, including their
+    /// configuration space. Without root, only the first 64 bytes of the
+    /// configuration space are recorded.
+    pub fn capture_with_config() -> Result<Self, PciEnumerationError> {
+        Self::capture_devices(true)
+    }
+
+    /// Internal function to take a snapshot, optionally including the
+    /// configuration space.
+    fn capture_devices(with_config: bool) -> Result<Self, PciEnumerationError> {
+        let mut devices: Vec<PciSnapshotDevice> = get_pci_list()?
+            .into_iter()
+            .map(|x| PciSnapshotDevice::capture(x, with_config))
+            .collect();
+        devices.sort_by(|a, b| a.hardware.cmp(&b.hardware));
+
+        Ok(PciSnapshot {
+            taken_at: SystemTime::now(),
+            devices,
+        })
+    }
+
+    /// Builds a snapshot from devices recorded elsewhere, e.g. loaded from a
+    /// file or received from another machine.
+    pub fn from_devices(mut devices: Vec<PciSnapshotDevice>, taken_at: SystemTime) -> Self {
+        devices.sort_by(|a, b| a.hardware.cmp(&b.hardware));
+        PciSnapshot { taken_at, devices }
+    }
+}
+
+/// Compares two snapshots, returning the devices that were added, removed,
+/// or changed between them.
+pub fn diff(old: &PciSnapshot, new: &PciSnapshot) -> PciSnapshotDiff {
+    let mut result = PciSnapshotDiff::default();
+
+    let mut old_by_address: BTreeMap<PciDeviceAddress, &PciSnapshotDevice> = BTreeMap::new();
+    let mut old_without_address: Vec<&PciSnapshotDevice> = Vec::new();
+    for device in &old.devices {
+        match device.hardware.address {
+            Some(address) => {
+                old_by_address.insert(address, device);
+            }
+            None => old_without_address.push(device),
+        }
+    }
+
+    for device in &new.devices {
+        match device.hardware.address {
+            Some(address) => match old_by_address.remove(&address) {
+                Some(old_device) => {
+                    let changes = old_device.changes(device);
+                    if !changes.is_empty() {
+                        result.changed.push(PciDeviceChange { address, changes });
+                    }
+                }
+                None => result.added.push(device.clone()),
+            },
+            // Without an address, the best we can do is look for an
+            // identical device.
+            None => match old_without_address
+                .iter()
+                .position(|x| x.hardware == device.hardware)
+            {
+                Some(index) => {
+                    old_without_address.remove(index);
+                }
+                None => result.added.push(device.clone()),
+            },
+        }
+    }
+
+    result.removed.extend(old_by_address.into_values().cloned());
+    result
+        .removed
+        .extend(old_without_address.into_iter().cloned());
+    result.removed.sort_by(|a, b| a.hardware.cmp(&b.hardware));
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a device at a given device number on bus 0.
+    fn device_at(device: u8, device_id: u16) -> PciDeviceHardware {
+        PciDeviceHardware {
+            address: Some(PciDeviceAddress {
+                domain: 0,
+                bus: 0,
+                device,
+                function: 0,
+            }),
+            vendor_id: 0x8086,
+            device_id,
+            ..Default::default()
+        }
+    }
+
+    /// Test comparing snapshots with added, removed, and changed devices.
+    #[test]
+    fn test_diff() {
+        let mut renegotiated = PciSnapshotDevice::from(device_at(2, 0x1572));
+        renegotiated.link_speed = Some("8.0 GT/s PCIe".to_owned());
+        let old = PciSnapshot::from_devices(
+            vec![
+                device_at(1, 0x100e).into(),
+                renegotiated.clone(),
+                device_at(3, 0x29c0).into(),
+            ],
+            SystemTime::UNIX_EPOCH,
+        );
+
+        let mut updated = device_at(1, 0x100e);
+        updated.revision_id = 2;
+        renegotiated.link_speed = Some("2.5 GT/s PCIe".to_owned());
+        let new = PciSnapshot::from_devices(
+            vec![
+                renegotiated.clone(),
+                device_at(4, 0x7010).into(),
+                updated.into(),
+            ],
+            SystemTime::now(),
+        );
+
+        let changes = diff(&old, &new);
+        assert_eq!(changes.added, vec![device_at(4, 0x7010).into()]);
+        assert_eq!(changes.removed, vec![device_at(3, 0x29c0).into()]);
+        assert_eq!(changes.changed.len(), 2);
+        assert_eq!(
+            changes.changed[0].changes,
+            vec![PciFieldChange::Revision(0, 2)]
+        );
+        assert_eq!(
+            changes.changed[1].changes,
+            vec![PciFieldChange::LinkSpeed(
+                Some("8.0 GT/s PCIe".to_owned()),
+                Some("2.5 GT/s PCIe".to_owned())
+            )]
+        );
+
+        assert!(diff(&new, &new).is_empty());
+    }
+
+    /// Test capturing a snapshot of the system. Devices can be hotplugged
+    /// at any time, so the snapshot is only compared against itself.
+    #[test]
+    fn test_capture() {
+        let snapshot = PciSnapshot::capture_with_config().unwrap();
+        println!("{:?}", snapshot.devices);
+        assert!(diff(&snapshot, &snapshot).is_empty());
+    }
+}